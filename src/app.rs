@@ -1,23 +1,62 @@
+use std::collections::BTreeMap;
+use std::env;
 use std::fmt::Display;
-use std::fs::{File, OpenOptions};
+use std::fs::File;
 use std::io::{Write, stdout};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
 use crate::{
+    brackets::jump_target,
     cli::CliOpt,
+    command::Command,
+    confirm::{Confirm, Question},
     event::{AppEvent, Event, EventHandler},
-    filesbuffers::{FilesBuffers, Move},
-    ui::FOOTER_SIZE,
+    filesbuffers::{BufferId, BufferKind, Edit, FileBuffer, FilesBuffers, Move},
+    jumplist::Location,
+    keyhints::{KEY_HINTS_DELAY, key_hints, key_hints_lines},
+    messages::{Messages, log_path},
+    minimap::{LINES_PER_ROW, first_line},
+    operator::{Motion, Operator, Range},
+    options::Options,
+    paths::autosession_path,
+    popup::{Anchor, CommandInput, Popup, PopupContent, PopupId, Popups, Size},
+    registers::{Register, Registers},
+    session::Session,
+    signs::Sign,
+    startscreen::{StartScreen, StartTarget},
+    state::{State, StateMark, StatePosition, StateRegister},
+    tabpage::{TabPage, TabPages},
+    text::{next_boundary, previous_boundary},
+    theme::{ColorSupport, DEFAULT_THEME, Group, Theme},
+    ui::{
+        buffer_at, bufferline_area, content_area, gutter_width, minimap_width, tab_at, views_areas,
+    },
+    view::{ScrollAnchor, Split, View},
 };
 use chrono::{DateTime, Local};
 use ratatui::{
     DefaultTerminal,
-    crossterm::event::{KeyCode, KeyEvent},
+    crossterm::{
+        event::{
+            DisableMouseCapture, EnableMouseCapture, Event as CrosstermEvent, KeyCode, KeyEvent,
+            KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+        },
+        execute,
+    },
+    layout::{Position, Rect},
 };
+use unicode_width::UnicodeWidthStr;
 
 pub const APP_NAME: &str = "Oxide";
 pub const EMPTY_STRING: &str = "";
+/// Namespace of the signs showing the marks.
+const MARKS_NAMESPACE: &str = "marks";
+/// Number of lines scrolled by a turn of the mouse wheel.
+const SCROLL_LINES: u16 = 3;
+/// Longest delay between the two clicks of a double click.
+const DOUBLE_CLICK: Duration = Duration::from_millis(500);
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum EditorMode {
@@ -26,6 +65,19 @@ pub enum EditorMode {
     Visual,
 }
 
+/// What is dragged with the left button of the mouse since it was pressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseDrag {
+    /// Selection of text, by lines when started in the gutter
+    Selection,
+    /// Separator following the view of the given index
+    Separator(usize),
+    /// Viewport of the current view, through its minimap
+    Minimap,
+    /// Label of a buffer in the bufferline, to reorder it
+    Buffer(BufferId),
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum CursorType {
     Block,
@@ -49,60 +101,203 @@ pub struct Editor {
     pub cli_opts: CliOpt,
     /// Files buffers
     pub buffers: FilesBuffers,
-    /// Current file path
-    pub current_file_path: String,
+    /// Tab pages, each one with its views on the files buffers
+    pub tabs: TabPages,
     /// Editor Mode
     pub editor_mode: EditorMode,
     /// Repetitions buffer
     pub repetitions: String,
-    /// Command popup
-    pub command_popup: CommandPopup,
+    /// Prefix of a multi-key sequence waiting for its next key
+    pub pending_keys: String,
+    /// When the pending keys started waiting for their next key
+    pending_since: Option<Instant>,
+    /// Popup showing the keys that can follow the pending ones
+    key_hints_popup: Option<PopupId>,
+    /// Registers holding yanked and deleted text
+    pub registers: Registers,
+    /// Register selected with `"` for the next yank, delete or paste
+    pub selected_register: Option<char>,
+    /// File marks, set with an uppercase letter
+    pub global_marks: BTreeMap<char, Location>,
+    /// Buffer showing the last `:marks` or `:messages` listing, reused by the next one
+    listing_buffer: Option<BufferId>,
+    /// Floating windows drawn over the views, the topmost focusable one taking the keys
+    pub popups: Popups,
+    /// Last searched pattern
+    pub search_pattern: String,
+    /// Was the last search made towards the start of the buffer?
+    pub search_backward: bool,
+    /// Auto-session of the current directory, saved when quitting
+    pub autosession_path: Option<PathBuf>,
+    /// Options shared by all the buffers
+    pub options: Options,
+    /// State loaded at startup, holding the histories, and saved back when quitting
+    pub state: State,
+    /// Start of the selection in visual mode
+    pub selection_start: Option<Location>,
+    /// Does the selection cover whole lines?
+    pub selection_linewise: bool,
+    /// Register the typed keys are recorded into, as a macro
+    pub recording: Option<char>,
+    /// Shown on the message line, and kept for `:messages`
+    pub messages: Messages,
+    /// Styles of the highlight groups, set by `:colorscheme`
+    pub theme: Theme,
+    /// Colours the terminal can show, the themes being downgraded to them
+    colors: ColorSupport,
+    /// Has anything changed since the last drawn frame?
+    pub dirty: bool,
+    /// Shown when starting without arguments, until the first edit
+    pub start_screen: Option<StartScreen>,
+    /// Terminal area of the last drawn frame
+    area: Rect,
+    /// Terminal position of the cursor in the last drawn frame
+    cursor_position: Position,
+    /// What the mouse is dragging, if anything
+    mouse_drag: Option<MouseDrag>,
+    /// Time and cell of the last click, to tell double clicks
+    last_click: Option<(Instant, Position)>,
     /// Saver lock
     lock: Mutex<()>,
     /// Last save date
     last_save: DateTime<Local>,
 }
 
-#[derive(Debug)]
-pub struct CommandPopup {
-    /// Is the popup shown ?
-    pub running: bool,
-    /// Command input field
-    pub input_field: String,
-}
-
 impl Editor {
     /// Constructs a new instance of [`App`].
     pub fn new(cli_opts: CliOpt) -> Self {
-        let mut current_file_path = EMPTY_STRING.to_string();
         let mut buffers = FilesBuffers::new();
+        let mut errors = vec![];
         for file_path in cli_opts.file() {
-            let mut actual_path = file_path.clone();
-            if let Ok(file_path) = Path::new(file_path).canonicalize() {
-                if let Some(file_path) = file_path.to_str() {
-                    actual_path = file_path.to_string();
-                }
+            if let Err(e) = buffers.open(file_path) {
+                errors.push(format!("Can't open {}: {}", file_path, e));
             }
-            if current_file_path == *EMPTY_STRING {
-                current_file_path = actual_path;
+        }
+        let state = State::load().unwrap_or_else(|e| {
+            errors.push(format!("Can't load the state of the previous runs: {}", e));
+            State::default()
+        });
+        let mut editor = Self::with_buffers(buffers, EventHandler::new(), cli_opts, state);
+        editor.messages = Messages::new(log_path());
+        for error in errors {
+            editor.messages.error(error);
+        }
+        match Theme::load(DEFAULT_THEME, editor.colors) {
+            Ok(theme) => editor.theme = theme,
+            Err(e) => editor.messages.error(e),
+        }
+        let file_paths: Vec<String> = editor
+            .buffers
+            .files
+            .values()
+            .filter_map(|file_buffer| file_buffer.file_path.clone())
+            .collect();
+        for file_path in file_paths {
+            editor.remember_recent_file(&file_path);
+        }
+        for (name, register) in &editor.state.registers {
+            if let Some(name) = name.chars().next() {
+                let register = Register {
+                    text: register
+                        .text
+                        .iter()
+                        .map(|line| line.chars().collect())
+                        .collect(),
+                    linewise: register.linewise,
+                };
+                editor.registers.registers.insert(name, register);
             }
-            buffers.init_file_buffer(current_file_path.to_string());
         }
+        let view = editor.tabs.current_mut().views.current_mut();
+        let file_buffer = editor.buffers.get_mut(view.buffer_id);
+        if let Some(position) = file_buffer
+            .file_path
+            .as_ref()
+            .and_then(|file_path| editor.state.positions.get(file_path))
+        {
+            let location = Location {
+                buffer_id: view.buffer_id,
+                line: position.line,
+                column: position.column,
+            };
+            view.go_to(location, file_buffer);
+        }
+        if let Some(session_path) = editor.cli_opts.session().cloned() {
+            editor.restore_session(Path::new(&session_path));
+        } else if editor.cli_opts.file().is_empty()
+            && let Some(session_path) = current_autosession_path()
+            && session_path.exists()
+        {
+            editor.restore_session(&session_path);
+            editor.autosession_path = Some(session_path);
+        } else if editor.cli_opts.file().is_empty() {
+            editor.start_screen = Some(StartScreen::new(&editor.state));
+        }
+        editor
+    }
+
+    /// Constructs an editor showing a scratch buffer of the given lines, for the tests.
+    #[cfg(test)]
+    pub fn with_lines(lines: &[&str]) -> Self {
+        let mut buffers = FilesBuffers::new();
+        buffers.create(FileBuffer::new(
+            BufferKind::Scratch,
+            None,
+            lines.iter().map(|line| line.chars().collect()).collect(),
+        ));
+        Self::with_buffers(
+            buffers,
+            EventHandler::detached(),
+            CliOpt::default(),
+            State::default(),
+        )
+    }
+
+    /// Constructs an editor showing the first of the given buffers, or a scratch buffer when
+    /// there is none, without reading anything from the disk.
+    fn with_buffers(
+        mut buffers: FilesBuffers,
+        events: EventHandler,
+        cli_opts: CliOpt,
+        state: State,
+    ) -> Self {
         if buffers.is_empty() {
-            buffers.init_file_buffer(EMPTY_STRING.to_string());
+            buffers.create(FileBuffer::new(BufferKind::Scratch, None, vec![]));
         }
+        let current_buffer_id = *buffers.files.keys().next().expect("No buffer opened");
         Self {
             running: true,
-            events: EventHandler::new(),
+            events,
             cli_opts,
             buffers,
-            current_file_path,
+            tabs: TabPages::new(TabPage::new(View::new(current_buffer_id))),
             editor_mode: EditorMode::Normal,
             repetitions: EMPTY_STRING.to_string(),
-            command_popup: CommandPopup {
-                running: false,
-                input_field: EMPTY_STRING.to_string(),
-            },
+            pending_keys: EMPTY_STRING.to_string(),
+            pending_since: None,
+            key_hints_popup: None,
+            registers: Registers::default(),
+            selected_register: None,
+            global_marks: BTreeMap::new(),
+            listing_buffer: None,
+            popups: Popups::default(),
+            search_pattern: EMPTY_STRING.to_string(),
+            search_backward: false,
+            autosession_path: None,
+            options: Options::default(),
+            state,
+            selection_start: None,
+            selection_linewise: false,
+            recording: None,
+            messages: Messages::default(),
+            theme: Theme::default(),
+            colors: ColorSupport::detect(),
+            dirty: true,
+            start_screen: None,
+            area: Rect::default(),
+            cursor_position: Position::default(),
+            mouse_drag: None,
+            last_click: None,
             lock: Mutex::new(()),
             last_save: Local::now(),
         }
@@ -111,76 +306,471 @@ impl Editor {
     /// Run the application's main loop.
     pub async fn run(mut self, mut terminal: DefaultTerminal) -> color_eyre::Result<()> {
         Self::set_cursor_type(CursorType::Block);
-        let terminal_height =
-            terminal.size().expect("Terminal should have size").height - FOOTER_SIZE - 1;
+        self.capture_mouse();
         while self.running {
-            terminal.draw(|frame| {
-                frame.render_widget(&self, frame.area());
-                let file_buffer = self.buffers.get_mut(self.current_file_path.clone());
-                let cursor_position = file_buffer.to_cursor_position();
-                frame.set_cursor_position((
-                    cursor_position.x,
-                    u16::min(cursor_position.y, terminal_height),
-                ));
-            })?;
-            let file_buffer = self.buffers.get_mut(self.current_file_path.clone());
+            if self.dirty {
+                terminal.draw(|frame| {
+                    let cursor_position = self.update_viewports(frame.area());
+                    frame.render_widget(&self, frame.area());
+                    frame.set_cursor_position(cursor_position);
+                })?;
+                self.dirty = false;
+            }
             match self.events.next().await? {
                 Event::Tick => self.tick(),
                 Event::Crossterm(event) => {
-                    if let crossterm::event::Event::Key(key_event) = event {
-                        if self.command_popup.running {
-                            self.handle_command_key_events(key_event)?
-                        } else {
-                            self.handle_key_events(key_event)?
-                        }
+                    self.dirty = true;
+                    match event {
+                        CrosstermEvent::Key(key_event) => self.handle_terminal_key(key_event)?,
+                        CrosstermEvent::Resize(width, height) => self.resize(width, height),
+                        CrosstermEvent::Mouse(mouse_event) => self.handle_mouse_events(mouse_event),
+                        _ => {}
                     }
                 }
                 Event::App(app_event) => {
+                    self.dirty = true;
                     let repetitions = self.repetitions.parse::<u16>().unwrap_or(1);
                     self.repetitions = EMPTY_STRING.to_string();
                     for _ in 0..repetitions {
-                        match app_event {
-                            AppEvent::Quit => self.running = false,
-                            AppEvent::NormalMode => {
-                                self.editor_mode = EditorMode::Insert;
-                                Self::set_cursor_type(CursorType::Line);
-                            }
-                            AppEvent::InsertMode => {
-                                self.editor_mode = EditorMode::Normal;
-                                Self::set_cursor_type(CursorType::Block);
-                            }
-                            AppEvent::CreateLine => file_buffer.create_line(),
-                            AppEvent::WriteAfterCursor(input) => file_buffer.insert_char(input),
-                            AppEvent::DeleteBeforeCursor => file_buffer.delete_previous_position(),
-                            AppEvent::MoveLeft => file_buffer.move_cursor(Move::Left),
-                            AppEvent::MoveUp => file_buffer.move_cursor(Move::Up),
-                            AppEvent::MoveRight => file_buffer.move_cursor(Move::Right),
-                            AppEvent::MoveDown => file_buffer.move_cursor(Move::Down),
-                            AppEvent::MoveToNextWord => file_buffer.move_to_next_word(),
-                            AppEvent::MoveToPreviousWord => file_buffer.move_to_previous_word(),
-                            AppEvent::CommandPopup => self.command_popup.running = true,
-                            AppEvent::WriteInCommandInput(input) => {
-                                self.command_popup.input_field += &input.to_string();
-                            }
-                            AppEvent::DeleteLastInCommandInput => {
-                                if !self.command_popup.input_field.is_empty() {
-                                    self.command_popup
-                                        .input_field
-                                        .remove(self.command_popup.input_field.len() - 1);
-                                }
-                            }
-                            AppEvent::ExecuteCommand => {
-                                //TODO : command processing
-                            }
-                        }
+                        self.handle_app_event(app_event.clone());
                     }
                     self.auto_save();
                 }
             }
         }
+        if let Some(session_path) = &self.autosession_path
+            && let Err(e) = Session::capture(&self).save(session_path)
+        {
+            self.messages.error(e.to_string());
+        }
+        self.save_state();
         Ok(())
     }
 
+    /// Captures the mouse, or leaves it to the terminal, following the `mouse` option.
+    fn capture_mouse(&mut self) {
+        let result = if self.options.mouse {
+            execute!(stdout(), EnableMouseCapture)
+        } else {
+            execute!(stdout(), DisableMouseCapture)
+        };
+        if let Err(e) = result {
+            self.messages
+                .error(format!("Can't capture the mouse: {}", e));
+        }
+    }
+
+    /// Dispatches a key pressed in the terminal, once the shown messages are acknowledged.
+    fn handle_terminal_key(&mut self, key_event: KeyEvent) -> color_eyre::Result<()> {
+        let prompted = self.messages.prompt;
+        self.messages.acknowledge();
+        // Enter, space and Esc only dismiss the messages waiting for Enter, as in Vim
+        if prompted
+            && matches!(
+                key_event.code,
+                KeyCode::Enter | KeyCode::Esc | KeyCode::Char(' ')
+            )
+        {
+            return Ok(());
+        }
+        match self.popups.focused().map(|popup| &popup.content) {
+            Some(PopupContent::Command(_)) => self.handle_command_key_events(key_event)?,
+            Some(PopupContent::Text(_)) => self.handle_popup_key_events(key_event),
+            Some(PopupContent::Confirm(_)) => self.handle_confirm_key_events(key_event),
+            None => self.handle_key_events(key_event)?,
+        }
+        if let Some(id) = self.key_hints_popup.take() {
+            self.popups.close(id);
+        }
+        self.pending_since = (!self.pending_keys.is_empty()).then(Instant::now);
+        Ok(())
+    }
+
+    /// Updates the state of [`App`] for an application event.
+    fn handle_app_event(&mut self, app_event: AppEvent) {
+        let (_, file_buffer) = self.current_view_and_buffer();
+        let is_modifiable = file_buffer.kind.is_modifiable();
+        match app_event {
+            AppEvent::CreateLine
+            | AppEvent::WriteAfterCursor(_)
+            | AppEvent::DeleteBeforeCursor
+            | AppEvent::Paste(_)
+            | AppEvent::Operate(Operator::Delete | Operator::Change, _)
+                if !is_modifiable => {}
+            AppEvent::Quit => self.quit(),
+            AppEvent::Answer(question, choice) => self.answer(question, choice),
+            AppEvent::NormalMode => {
+                self.start_screen = None;
+                self.editor_mode = EditorMode::Insert;
+                Self::set_cursor_type(CursorType::Line);
+            }
+            AppEvent::InsertMode => {
+                self.editor_mode = EditorMode::Normal;
+                Self::set_cursor_type(CursorType::Block);
+                let (view, file_buffer) = self.current_view_and_buffer();
+                file_buffer.marks.insert('^', view.location());
+            }
+            AppEvent::CreateLine => {
+                let (view, file_buffer) = self.current_view_and_buffer();
+                let line = view.current_line;
+                let edit = file_buffer.create_line(line);
+                self.apply_edit(edit);
+                let view = self.tabs.current_mut().views.current_mut();
+                view.current_line = line + 1;
+                view.current_column = 0;
+                view.desired_column = 0;
+            }
+            AppEvent::WriteAfterCursor(input) => {
+                let (view, file_buffer) = self.current_view_and_buffer();
+                let edit = file_buffer.insert_char(view.current_line, view.current_column, input);
+                self.apply_edit(edit);
+                self.remember_column();
+            }
+            AppEvent::DeleteBeforeCursor => {
+                let (view, file_buffer) = self.current_view_and_buffer();
+                let (line, column) = (view.current_line, view.current_column);
+                if column > 0 {
+                    // Removes the whole grapheme, with its combining characters
+                    let start = previous_boundary(file_buffer.line(line), column);
+                    let edit = file_buffer.delete_chars(line, start, column - start);
+                    self.apply_edit(edit);
+                } else if line > 0 {
+                    let previous_line_len = file_buffer.line_len(line - 1);
+                    let edit = file_buffer.delete_line(line);
+                    self.apply_edit(edit);
+                    let view = self.tabs.current_mut().views.current_mut();
+                    view.current_line = line - 1;
+                    view.current_column = previous_line_len;
+                }
+                self.remember_column();
+            }
+            AppEvent::MoveLeft => self.move_cursor(Move::Left),
+            AppEvent::MoveUp => self.move_cursor(Move::Up),
+            AppEvent::MoveRight => self.move_cursor(Move::Right),
+            AppEvent::MoveDown => self.move_cursor(Move::Down),
+            AppEvent::MoveDisplayLine(down) => {
+                let view = self.tabs.current_mut().views.current_mut();
+                let file_buffer = self.buffers.get_mut(view.buffer_id);
+                view.move_display_line(file_buffer, &self.options, down);
+            }
+            AppEvent::ScrollCursorLine(anchor) => {
+                let views = &self.tabs.current().views;
+                let height =
+                    views_areas(views, content_area(self, self.area))[views.current].height;
+                let view = self.tabs.current_mut().views.current_mut();
+                view.scroll_y = match anchor {
+                    ScrollAnchor::Top => view.current_line,
+                    ScrollAnchor::Center => view.current_line.saturating_sub(height / 2),
+                    ScrollAnchor::Bottom => {
+                        view.current_line.saturating_sub(height.saturating_sub(1))
+                    }
+                };
+            }
+            AppEvent::MoveToNextWord => {
+                let (view, file_buffer) = self.current_view_and_buffer();
+                view.move_to_next_word(file_buffer);
+            }
+            AppEvent::MoveToPreviousWord => {
+                let (view, file_buffer) = self.current_view_and_buffer();
+                view.move_to_previous_word(file_buffer);
+            }
+            AppEvent::OpenListingEntry => {
+                let (view, file_buffer) = self.current_view_and_buffer();
+                if file_buffer.kind != BufferKind::Listing {
+                    return;
+                }
+                let entry: String = file_buffer.line(view.current_line).iter().collect();
+                if let Some(directory_path) = &file_buffer.file_path {
+                    let entry_path = Path::new(directory_path).join(entry);
+                    if let Some(buffer_id) = self.open_buffer(&entry_path.to_string_lossy()) {
+                        self.show_buffer(buffer_id);
+                    }
+                }
+            }
+            AppEvent::OpenStartEntry(key) => {
+                let Some(entry) = self
+                    .start_screen
+                    .as_ref()
+                    .and_then(|start_screen| start_screen.entry(key))
+                else {
+                    return;
+                };
+                match entry.target.clone() {
+                    StartTarget::File(file_path) => {
+                        if let Some(buffer_id) = self.open_buffer(&file_path) {
+                            self.show_buffer(buffer_id);
+                        }
+                    }
+                    StartTarget::Session(session_path) => {
+                        self.start_screen = None;
+                        self.restore_session(Path::new(&session_path));
+                    }
+                }
+            }
+            AppEvent::GoToLine(line) => {
+                let (view, file_buffer) = self.current_view_and_buffer();
+                let last_line = file_buffer.lines_number.saturating_sub(1);
+                let line = line.map_or(last_line, |line| line.saturating_sub(1));
+                let location = Location {
+                    buffer_id: view.buffer_id,
+                    line: u16::min(line, last_line),
+                    column: 0,
+                };
+                self.jump_to(location);
+            }
+            AppEvent::MatchingBracket => {
+                let (view, file_buffer) = self.current_view_and_buffer();
+                let cursor = (view.current_line, view.current_column);
+                if let Some((line, column)) =
+                    jump_target(&file_buffer.file, cursor, &file_buffer.options.filetype)
+                {
+                    let location = Location {
+                        buffer_id: view.buffer_id,
+                        line,
+                        column,
+                    };
+                    self.jump_to(location);
+                }
+            }
+            AppEvent::Search(pattern, backward) => {
+                self.state.search_history.push(&pattern);
+                self.search_pattern = pattern;
+                self.search_backward = backward;
+                self.search(backward);
+            }
+            AppEvent::NextMatch => self.search(self.search_backward),
+            AppEvent::PreviousMatch => self.search(!self.search_backward),
+            AppEvent::JumpBack => {
+                let view = self.tabs.current_mut().views.current_mut();
+                let current = view.location();
+                if let Some(location) = view.jumps.back(Some(current)) {
+                    view.go_to(location, self.buffers.get_mut(location.buffer_id));
+                }
+            }
+            AppEvent::JumpForward => {
+                let view = self.tabs.current_mut().views.current_mut();
+                if let Some(location) = view.jumps.forward() {
+                    view.go_to(location, self.buffers.get_mut(location.buffer_id));
+                }
+            }
+            AppEvent::OlderChange => {
+                let (view, file_buffer) = self.current_view_and_buffer();
+                if let Some(location) = file_buffer.changes.back(None) {
+                    view.go_to(location, file_buffer);
+                }
+            }
+            AppEvent::NewerChange => {
+                let (view, file_buffer) = self.current_view_and_buffer();
+                if let Some(location) = file_buffer.changes.forward() {
+                    view.go_to(location, file_buffer);
+                }
+            }
+            AppEvent::Operate(operator, motion) => self.operate(operator, motion),
+            AppEvent::StopVisual => self.stop_visual(),
+            AppEvent::Paste(before) => self.paste(before),
+            AppEvent::SetMark(name) => {
+                let (view, file_buffer) = self.current_view_and_buffer();
+                let location = view.location();
+                if name.is_ascii_lowercase() {
+                    file_buffer.marks.insert(name, location);
+                    self.place_mark_signs();
+                } else if name.is_ascii_uppercase() {
+                    self.global_marks.insert(name, location);
+                }
+            }
+            AppEvent::JumpToMark(name, exact) => {
+                if let Some(mut location) = self.mark(name) {
+                    if !exact {
+                        let line = self.buffers.get_mut(location.buffer_id).line(location.line);
+                        location.column =
+                            line.iter().take_while(|c| c.is_whitespace()).count() as u16;
+                    }
+                    self.jump_to(location);
+                }
+            }
+            AppEvent::AlternateBuffer => {
+                if let Some(alternate) = self.tabs.current().views.current().alternate {
+                    self.jump_to(alternate);
+                }
+            }
+            AppEvent::SplitView(split) => self.tabs.current_mut().views.split(split),
+            AppEvent::CloseView => {
+                self.tabs.current_mut().views.close();
+            }
+            AppEvent::FocusNextView => self.tabs.current_mut().views.focus_next(),
+            AppEvent::FocusPreviousView => self.tabs.current_mut().views.focus_previous(),
+            AppEvent::NextTab => self.tabs.next(),
+            AppEvent::PreviousTab => self.tabs.previous(),
+            AppEvent::CommandPopup => self.open_command_popup(':'),
+            AppEvent::SearchPopup(backward) => {
+                self.open_command_popup(if backward { '?' } else { '/' })
+            }
+            AppEvent::WriteInCommandInput(input) => {
+                if let Some(command_input) = self.popups.command_input_mut() {
+                    command_input.input_field.push(input);
+                }
+            }
+            AppEvent::DeleteLastInCommandInput => {
+                if let Some(command_input) = self.popups.command_input_mut() {
+                    command_input.input_field.pop();
+                }
+            }
+            AppEvent::BrowseHistory(older) => {
+                let Some(command_input) = self.popups.command_input_mut() else {
+                    return;
+                };
+                let history = if command_input.prompt == ':' {
+                    &self.state.command_history
+                } else {
+                    &self.state.search_history
+                };
+                let history_index = match (older, command_input.history_index) {
+                    (true, None) => Some(0),
+                    (true, Some(index)) => Some(index + 1),
+                    (false, None | Some(0)) => None,
+                    (false, Some(index)) => Some(index - 1),
+                };
+                match history_index {
+                    Some(index) => {
+                        if let Some(entry) = history.get(index) {
+                            command_input.input_field = entry.to_string();
+                            command_input.history_index = history_index;
+                        }
+                    }
+                    None => {
+                        command_input.input_field = EMPTY_STRING.to_string();
+                        command_input.history_index = None;
+                    }
+                }
+            }
+            AppEvent::ExecuteCommand(input) => {
+                self.state.command_history.push(&input);
+                self.execute_command(Command::parse(&input))
+            }
+        }
+    }
+
+    /// Executes a command typed in the command popup.
+    fn execute_command(&mut self, command: Command) {
+        self.start_screen = None;
+        match command.clone() {
+            Command::Quit => {
+                if !self.tabs.current_mut().views.close() && !self.tabs.close() {
+                    self.quit();
+                }
+            }
+            Command::Split => self.tabs.current_mut().views.split(Split::Horizontal),
+            Command::VerticalSplit => self.tabs.current_mut().views.split(Split::Vertical),
+            Command::Close => {
+                self.tabs.current_mut().views.close();
+            }
+            Command::Edit(file_path) => {
+                if let Some(file_path) = file_path
+                    && let Some(buffer_id) = self.open_buffer(&file_path)
+                {
+                    self.show_buffer(buffer_id);
+                }
+            }
+            Command::EditNew => {
+                let buffer_id = self.create_scratch_buffer();
+                self.show_buffer(buffer_id);
+            }
+            Command::New | Command::VerticalNew => {
+                let buffer_id = self.create_scratch_buffer();
+                self.tabs
+                    .current_mut()
+                    .views
+                    .split(if command == Command::New {
+                        Split::Horizontal
+                    } else {
+                        Split::Vertical
+                    });
+                self.show_buffer(buffer_id);
+            }
+            Command::TabNew(file_path) => {
+                let buffer_id = match file_path {
+                    Some(file_path) => self.open_buffer(&file_path),
+                    None => Some(self.create_scratch_buffer()),
+                };
+                let Some(buffer_id) = buffer_id else {
+                    return;
+                };
+                self.tabs.open(TabPage::new(View::new(buffer_id)));
+            }
+            Command::Marks => self.show_listing(self.marks_listing()),
+            Command::Set(arguments) | Command::SetLocal(arguments) => {
+                let buffer_id = self.tabs.current().views.current().buffer_id;
+                let mouse = self.options.mouse;
+                for argument in arguments {
+                    // `:setlocal` only changes the options of the current buffer
+                    let result =
+                        if matches!(command, Command::Set(_)) && Options::is_global(&argument) {
+                            self.options.set(&argument)
+                        } else {
+                            self.buffers.get_mut(buffer_id).options.set(&argument)
+                        };
+                    if let Err(e) = result {
+                        self.messages.error(e);
+                    }
+                }
+                if self.options.mouse != mouse {
+                    self.capture_mouse();
+                }
+            }
+            Command::ChangeDirectory(directory) => {
+                let directory = directory.or_else(|| env::var("HOME").ok());
+                if let Some(directory) = directory
+                    && let Err(e) = env::set_current_dir(&directory)
+                {
+                    self.messages
+                        .error(format!("Can't change directory to {}: {}", directory, e));
+                }
+            }
+            Command::MakeSession(session_path) => {
+                let session_path = match session_path {
+                    Some(session_path) => Some(PathBuf::from(session_path)),
+                    None => {
+                        self.autosession_path = current_autosession_path();
+                        self.autosession_path.clone()
+                    }
+                };
+                let Some(session_path) = session_path else {
+                    self.messages
+                        .warning("No state directory to save the session into");
+                    return;
+                };
+                match Session::capture(self).save(&session_path) {
+                    Ok(()) => {
+                        let session_path = session_path.to_string_lossy();
+                        self.messages
+                            .info(format!("Session saved to {}", session_path));
+                        self.state.recent_sessions.push(&session_path);
+                    }
+                    Err(e) => self.messages.error(e.to_string()),
+                }
+            }
+            Command::BufferNext => self.cycle_buffer(true),
+            Command::BufferPrevious => self.cycle_buffer(false),
+            Command::TabNext => self.tabs.next(),
+            Command::TabPrevious => self.tabs.previous(),
+            Command::TabClose => {
+                self.tabs.close();
+            }
+            Command::Messages => self.show_listing(self.messages.listing()),
+            Command::ColorScheme(None) => self.messages.info(self.theme.name.clone()),
+            Command::ColorScheme(Some(name)) => match Theme::load(&name, self.colors) {
+                Ok(theme) => {
+                    self.theme = theme;
+                    self.place_mark_signs();
+                }
+                Err(e) => self.messages.error(e),
+            },
+            Command::Unknown(input) => self
+                .messages
+                .error(format!("Not an editor command: {}", input)),
+        }
+    }
+
     /// Handles the key events and updates the state of command popup
     pub fn handle_command_key_events(&mut self, key_event: KeyEvent) -> color_eyre::Result<()> {
         match key_event.code {
@@ -191,8 +781,18 @@ impl Editor {
             KeyCode::Backspace => {
                 self.events.send(AppEvent::DeleteLastInCommandInput);
             }
+            KeyCode::Up => self.events.send(AppEvent::BrowseHistory(true)),
+            KeyCode::Down => self.events.send(AppEvent::BrowseHistory(false)),
             KeyCode::Enter => {
-                self.events.send(AppEvent::ExecuteCommand);
+                let Some(command_input) = self.popups.command_input() else {
+                    return Ok(());
+                };
+                let input = command_input.input_field.clone();
+                match command_input.prompt {
+                    '/' => self.events.send(AppEvent::Search(input, false)),
+                    '?' => self.events.send(AppEvent::Search(input, true)),
+                    _ => self.events.send(AppEvent::ExecuteCommand(input)),
+                }
                 self.exit_popup();
             }
             _ => {}
@@ -200,9 +800,176 @@ impl Editor {
         Ok(())
     }
 
+    /// Handles the keys scrolling or closing the focused popup.
+    fn handle_popup_key_events(&mut self, key_event: KeyEvent) {
+        let Some(popup) = self.popups.focused() else {
+            return;
+        };
+        let height = self.popup_area(popup).height.saturating_sub(2) as usize;
+        let last_scroll = popup.lines_number().saturating_sub(height);
+        let Some(popup) = self.popups.focused_mut() else {
+            return;
+        };
+        popup.scroll = usize::min(popup.scroll, last_scroll);
+        match key_event.code {
+            KeyCode::Esc | KeyCode::Char('q') => self.exit_popup(),
+            KeyCode::Down | KeyCode::Char('j') => {
+                popup.scroll = usize::min(popup.scroll + 1, last_scroll)
+            }
+            KeyCode::Up | KeyCode::Char('k') => popup.scroll = popup.scroll.saturating_sub(1),
+            KeyCode::PageDown => popup.scroll = usize::min(popup.scroll + height, last_scroll),
+            KeyCode::PageUp => popup.scroll = popup.scroll.saturating_sub(height),
+            _ => {}
+        }
+    }
+
+    /// Handles the keys answering the focused confirm dialog, the other keys being ignored.
+    fn handle_confirm_key_events(&mut self, key_event: KeyEvent) {
+        let Some(PopupContent::Confirm(confirm)) =
+            self.popups.focused().map(|popup| &popup.content)
+        else {
+            return;
+        };
+        let choice = match key_event.code {
+            KeyCode::Esc => None,
+            KeyCode::Enter => Some(confirm.default),
+            KeyCode::Char(key) => match confirm.choice(key) {
+                Some(choice) => Some(choice),
+                None => return,
+            },
+            _ => return,
+        };
+        self.events.send(AppEvent::Answer(confirm.question, choice));
+        self.exit_popup();
+    }
+
+    /// Asks a question in a dialog taking all the keys until it is answered. The choice comes
+    /// back as an [`AppEvent::Answer`], handled by [`Editor::answer`].
+    pub fn confirm(&mut self, confirm: Confirm) {
+        let lines = confirm.lines();
+        let width = lines.iter().map(|line| line.width()).max().unwrap_or(0) as u16 + 4;
+        let popup = Popup {
+            z_index: 2,
+            ..Popup::new(
+                " Confirm ",
+                Anchor::Editor,
+                Size::Cells(width),
+                Size::Cells(lines.len() as u16 + 2),
+                PopupContent::Confirm(confirm),
+            )
+        };
+        self.popups.open(popup);
+    }
+
+    /// Goes on with what asked a question, once answered.
+    fn answer(&mut self, question: Question, choice: Option<usize>) {
+        match (question, choice) {
+            (Question::QuitWithChanges, Some(0)) => {
+                let _guard = self.lock.lock().unwrap();
+                write_buffers(&mut self.buffers, &mut self.messages);
+                let unsaved: Vec<&FileBuffer> = self
+                    .buffers
+                    .files
+                    .values()
+                    .filter(|file_buffer| file_buffer.modified)
+                    .collect();
+                let unnamed: Vec<String> = unsaved
+                    .iter()
+                    .filter(|file_buffer| !file_buffer.can_save())
+                    .map(|file_buffer| file_buffer.name())
+                    .collect();
+                if !unnamed.is_empty() {
+                    self.messages
+                        .error(format!("No file name for {}", unnamed.join(", ")));
+                }
+                // The buffers that couldn't be written were reported by `write_buffers`
+                self.running = !unsaved.is_empty();
+            }
+            (Question::QuitWithChanges, Some(1)) => self.running = false,
+            (Question::QuitWithChanges, _) => {}
+        }
+    }
+
+    /// Quits, once confirmed when buffers have changes not written yet.
+    fn quit(&mut self) {
+        // A count repeats the event, which must not stack the dialogs
+        if self.popups.asks(Question::QuitWithChanges) {
+            return;
+        }
+        let modified: Vec<String> = self
+            .buffers
+            .files
+            .values()
+            .filter(|file_buffer| file_buffer.modified)
+            .map(FileBuffer::name)
+            .collect();
+        if modified.is_empty() {
+            self.running = false;
+            return;
+        }
+        self.confirm(Confirm::new(
+            Question::QuitWithChanges,
+            format!("Save changes to {} before quitting?", modified.join(", ")),
+            &["&Yes", "&No", "&Cancel"],
+        ));
+    }
+
+    /// Area of a popup in the last drawn frame.
+    pub fn popup_area(&self, popup: &Popup) -> Rect {
+        let views_areas = views_areas(&self.tabs.current().views, content_area(self, self.area));
+        popup.area(self.area, self.cursor_position, &views_areas)
+    }
+
+    fn open_command_popup(&mut self, prompt: char) {
+        let title = if prompt == ':' {
+            "Command pane"
+        } else {
+            "Search"
+        };
+        let command_input = CommandInput {
+            prompt,
+            ..CommandInput::default()
+        };
+        self.popups.open(Popup::new(
+            title,
+            Anchor::Editor,
+            Size::Percent(60),
+            Size::Cells(3),
+            PopupContent::Command(command_input),
+        ));
+    }
+
     /// Handles the key events and updates the state of [`App`].
     pub fn handle_key_events(&mut self, key_event: KeyEvent) -> color_eyre::Result<()> {
+        if !self.pending_keys.is_empty() {
+            return self.handle_pending_key_events(key_event);
+        }
         match key_event.code {
+            KeyCode::Char(input)
+                if self.repetitions.is_empty()
+                    && self
+                        .start_screen
+                        .as_ref()
+                        .is_some_and(|start_screen| start_screen.entry(input).is_some()) =>
+            {
+                self.events.send(AppEvent::OpenStartEntry(input))
+            }
+            KeyCode::Char('o')
+                if self.editor_mode != EditorMode::Insert
+                    && key_event.modifiers == KeyModifiers::CONTROL =>
+            {
+                self.events.send(AppEvent::JumpBack)
+            }
+            // Ctrl-I is received as a tab
+            KeyCode::Tab if self.editor_mode != EditorMode::Insert => {
+                self.events.send(AppEvent::JumpForward)
+            }
+            KeyCode::Char('^' | '6')
+                if self.editor_mode != EditorMode::Insert
+                    && key_event.modifiers == KeyModifiers::CONTROL =>
+            {
+                self.events.send(AppEvent::AlternateBuffer)
+            }
             KeyCode::Char(input)
                 if self.editor_mode != EditorMode::Insert && input.is_ascii_digit() =>
             {
@@ -224,6 +991,9 @@ impl Editor {
             KeyCode::Esc if self.editor_mode == EditorMode::Insert => {
                 self.events.send(AppEvent::InsertMode);
             }
+            KeyCode::Esc if self.editor_mode == EditorMode::Visual => {
+                self.events.send(AppEvent::StopVisual)
+            }
             KeyCode::Esc if self.editor_mode == EditorMode::Normal => {
                 self.events.send(AppEvent::Quit)
             }
@@ -233,6 +1003,9 @@ impl Editor {
             KeyCode::Enter if self.editor_mode == EditorMode::Insert => {
                 self.events.send(AppEvent::CreateLine)
             }
+            KeyCode::Enter if self.editor_mode == EditorMode::Normal => {
+                self.events.send(AppEvent::OpenListingEntry)
+            }
             KeyCode::Left | KeyCode::Char('h') if self.editor_mode != EditorMode::Insert => {
                 self.events.send(AppEvent::MoveLeft)
             }
@@ -249,12 +1022,69 @@ impl Editor {
             KeyCode::Up => self.events.send(AppEvent::MoveUp),
             KeyCode::Right => self.events.send(AppEvent::MoveRight),
             KeyCode::Down => self.events.send(AppEvent::MoveDown),
+            KeyCode::Char('w')
+                if self.editor_mode != EditorMode::Insert
+                    && key_event.modifiers == KeyModifiers::CONTROL =>
+            {
+                self.pending_keys = "<C-w>".to_string()
+            }
             KeyCode::Char('w') if self.editor_mode != EditorMode::Insert => {
                 self.events.send(AppEvent::MoveToNextWord)
             }
             KeyCode::Char('b') if self.editor_mode != EditorMode::Insert => {
                 self.events.send(AppEvent::MoveToPreviousWord)
             }
+            KeyCode::Char('g') if self.editor_mode != EditorMode::Insert => {
+                self.pending_keys = "g".to_string()
+            }
+            KeyCode::Char('z') if self.editor_mode != EditorMode::Insert => {
+                self.pending_keys = "z".to_string()
+            }
+            KeyCode::Char('G') if self.editor_mode != EditorMode::Insert => {
+                let line = std::mem::take(&mut self.repetitions).parse::<u16>().ok();
+                self.events.send(AppEvent::GoToLine(line))
+            }
+            // With a count, `%` goes to that percentage of the buffer instead
+            KeyCode::Char('%') if self.editor_mode != EditorMode::Insert => {
+                let count = std::mem::take(&mut self.repetitions);
+                let (_, file_buffer) = self.current_view_and_buffer();
+                let lines_number = file_buffer.lines_number as u32;
+                // As in Vim, counts over 100 are rejected
+                let line = count
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|percent| *percent <= 100)
+                    .and_then(|percent| percent.checked_mul(lines_number))
+                    .and_then(|line| u16::try_from(line.div_ceil(100).max(1)).ok());
+                match line {
+                    _ if count.is_empty() => self.events.send(AppEvent::MatchingBracket),
+                    Some(line) => self.events.send(AppEvent::GoToLine(Some(line))),
+                    None => {}
+                }
+            }
+            KeyCode::Char('/') if self.editor_mode != EditorMode::Insert => {
+                self.events.send(AppEvent::SearchPopup(false))
+            }
+            KeyCode::Char('?') if self.editor_mode != EditorMode::Insert => {
+                self.events.send(AppEvent::SearchPopup(true))
+            }
+            KeyCode::Char('n') if self.editor_mode != EditorMode::Insert => {
+                self.events.send(AppEvent::NextMatch)
+            }
+            KeyCode::Char('N') if self.editor_mode != EditorMode::Insert => {
+                self.events.send(AppEvent::PreviousMatch)
+            }
+            KeyCode::Char(key @ ('d' | 'y' | 'c' | 'm' | '\'' | '`' | '"'))
+                if self.editor_mode != EditorMode::Insert =>
+            {
+                self.pending_keys = key.to_string()
+            }
+            KeyCode::Char('p') if self.editor_mode != EditorMode::Insert => {
+                self.events.send(AppEvent::Paste(false))
+            }
+            KeyCode::Char('P') if self.editor_mode != EditorMode::Insert => {
+                self.events.send(AppEvent::Paste(true))
+            }
             KeyCode::Char(':') if self.editor_mode != EditorMode::Insert => {
                 self.events.send(AppEvent::CommandPopup)
             }
@@ -267,6 +1097,308 @@ impl Editor {
         Ok(())
     }
 
+    /// Handles the key completing the pending multi-key sequence.
+    fn handle_pending_key_events(&mut self, key_event: KeyEvent) -> color_eyre::Result<()> {
+        let pending_keys = std::mem::take(&mut self.pending_keys);
+        match (pending_keys.as_str(), key_event.code) {
+            ("<C-w>", KeyCode::Char('w')) => self.events.send(AppEvent::FocusNextView),
+            ("<C-w>", KeyCode::Char('W')) => self.events.send(AppEvent::FocusPreviousView),
+            ("<C-w>", KeyCode::Char('s')) => {
+                self.events.send(AppEvent::SplitView(Split::Horizontal))
+            }
+            ("<C-w>", KeyCode::Char('v')) => self.events.send(AppEvent::SplitView(Split::Vertical)),
+            ("<C-w>", KeyCode::Char('c' | 'q')) => self.events.send(AppEvent::CloseView),
+            ("g", KeyCode::Char('t')) => self.events.send(AppEvent::NextTab),
+            ("g", KeyCode::Char('T')) => self.events.send(AppEvent::PreviousTab),
+            ("g", KeyCode::Char('g')) => {
+                let line = std::mem::take(&mut self.repetitions).parse::<u16>();
+                self.events
+                    .send(AppEvent::GoToLine(Some(line.unwrap_or(1))))
+            }
+            ("g", KeyCode::Char('j')) => self.events.send(AppEvent::MoveDisplayLine(true)),
+            ("g", KeyCode::Char('k')) => self.events.send(AppEvent::MoveDisplayLine(false)),
+            ("g", KeyCode::Char(';')) => self.events.send(AppEvent::OlderChange),
+            ("g", KeyCode::Char(',')) => self.events.send(AppEvent::NewerChange),
+            ("z", KeyCode::Char('t')) => self
+                .events
+                .send(AppEvent::ScrollCursorLine(ScrollAnchor::Top)),
+            ("z", KeyCode::Char('z')) => self
+                .events
+                .send(AppEvent::ScrollCursorLine(ScrollAnchor::Center)),
+            ("z", KeyCode::Char('b')) => self
+                .events
+                .send(AppEvent::ScrollCursorLine(ScrollAnchor::Bottom)),
+            ("m", KeyCode::Char(name)) => self.events.send(AppEvent::SetMark(name)),
+            ("'", KeyCode::Char(name)) => self.events.send(AppEvent::JumpToMark(name, false)),
+            ("`", KeyCode::Char(name)) => self.events.send(AppEvent::JumpToMark(name, true)),
+            ("\"", KeyCode::Char(name)) if Registers::is_valid(name) => {
+                self.selected_register = Some(name)
+            }
+            _ => {
+                let operator = pending_keys.chars().next().and_then(Operator::from_key);
+                if let Some(operator) = operator {
+                    self.handle_operator_key_events(operator, &pending_keys[1..], key_event);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Handles the motion keys following an operator key.
+    fn handle_operator_key_events(
+        &mut self,
+        operator: Operator,
+        motion_keys: &str,
+        key_event: KeyEvent,
+    ) {
+        let motion = match (motion_keys, key_event.code) {
+            ("", KeyCode::Left | KeyCode::Char('h')) => Motion::Left,
+            ("", KeyCode::Up | KeyCode::Char('k')) => Motion::Up,
+            ("", KeyCode::Right | KeyCode::Char('l')) => Motion::Right,
+            ("", KeyCode::Down | KeyCode::Char('j')) => Motion::Down,
+            ("", KeyCode::Char('w')) => Motion::NextWord,
+            ("", KeyCode::Char('b')) => Motion::PreviousWord,
+            ("", KeyCode::Char('G')) => Motion::GoToLine(None),
+            ("", KeyCode::Char('%')) => Motion::MatchingBracket,
+            ("", KeyCode::Char(key)) if key == operator.key() => Motion::Line,
+            ("", KeyCode::Char(key @ ('g' | '\'' | '`'))) => {
+                self.pending_keys = format!("{}{}", operator.key(), key);
+                return;
+            }
+            ("g", KeyCode::Char('g')) => Motion::GoToLine(Some(1)),
+            ("'", KeyCode::Char(name)) => Motion::Mark(name, false),
+            ("`", KeyCode::Char(name)) => Motion::Mark(name, true),
+            _ => return,
+        };
+        self.events.send(AppEvent::Operate(operator, motion));
+    }
+
+    /// Handles the mouse events: clicks, drags and scrolls of the wheel.
+    fn handle_mouse_events(&mut self, mouse_event: MouseEvent) {
+        if self.start_screen.is_some() || self.popups.focused().is_some() {
+            return;
+        }
+        let position = Position::new(mouse_event.column, mouse_event.row);
+        match mouse_event.kind {
+            MouseEventKind::ScrollDown | MouseEventKind::ScrollUp => {
+                if let Some((index, area)) = self.view_at(position) {
+                    self.scroll_view(index, area, mouse_event.kind == MouseEventKind::ScrollDown);
+                }
+            }
+            MouseEventKind::Down(MouseButton::Left) => self.click(position),
+            MouseEventKind::Drag(MouseButton::Left) => self.drag(position),
+            MouseEventKind::Up(MouseButton::Left) => self.mouse_drag = None,
+            _ => {}
+        }
+    }
+
+    /// Index and area of the view of the current tab page shown at a cell.
+    fn view_at(&self, position: Position) -> Option<(usize, Rect)> {
+        let areas = views_areas(&self.tabs.current().views, content_area(self, self.area));
+        areas
+            .into_iter()
+            .enumerate()
+            .find(|(_, area)| area.contains(position))
+    }
+
+    /// Index of the view followed by the separator drawn at a cell.
+    fn separator_at(&self, position: Position) -> Option<usize> {
+        let views = &self.tabs.current().views;
+        let areas = views_areas(views, content_area(self, self.area));
+        let last = areas.len().checked_sub(1)?;
+        areas[..last].iter().position(|area| match views.split {
+            Split::Horizontal => {
+                position.y == area.bottom() && (area.x..area.right()).contains(&position.x)
+            }
+            Split::Vertical => {
+                position.x == area.right() && (area.y..area.bottom()).contains(&position.y)
+            }
+        })
+    }
+
+    /// Scrolls a view by a few lines, keeping its cursor within it.
+    fn scroll_view(&mut self, index: usize, area: Rect, down: bool) {
+        let view = &mut self.tabs.current_mut().views.list[index];
+        let file_buffer = self.buffers.get(view.buffer_id);
+        let last_line = file_buffer.lines_number.saturating_sub(1);
+        view.scroll_y = if down {
+            u16::min(view.scroll_y + SCROLL_LINES, last_line)
+        } else {
+            view.scroll_y.saturating_sub(SCROLL_LINES)
+        };
+        let bottom = u16::min(view.scroll_y + area.height.saturating_sub(1), last_line);
+        view.current_line = view.current_line.clamp(view.scroll_y, bottom);
+        view.restore_desired_column(file_buffer);
+    }
+
+    /// Handles a press of the left button: switches tab page, focuses a view, starts dragging
+    /// a separator, moves the cursor or starts selecting.
+    fn click(&mut self, position: Position) {
+        let double_click = self
+            .last_click
+            .is_some_and(|(time, last)| last == position && time.elapsed() < DOUBLE_CLICK);
+        self.last_click = Some((Instant::now(), position));
+        if self.tabs.list.len() > 1 && position.y == self.area.y {
+            if let Some(index) = tab_at(self, self.area, position.x) {
+                self.stop_visual();
+                self.tabs.current = index;
+            }
+            return;
+        }
+        if let Some(bufferline_area) = bufferline_area(self, self.area)
+            && bufferline_area.contains(position)
+        {
+            if let Some(buffer_id) = buffer_at(self, bufferline_area, position.x) {
+                self.stop_visual();
+                if buffer_id != self.tabs.current().views.current().buffer_id {
+                    self.show_buffer(buffer_id);
+                }
+                self.mouse_drag = Some(MouseDrag::Buffer(buffer_id));
+            }
+            return;
+        }
+        if let Some(index) = self.separator_at(position) {
+            self.mouse_drag = Some(MouseDrag::Separator(index));
+            return;
+        }
+        let Some((index, area)) = self.view_at(position) else {
+            return;
+        };
+        if self.tabs.current().views.current != index {
+            self.stop_visual();
+            self.tabs.current_mut().views.current = index;
+        }
+        let view = self.tabs.current().views.current();
+        let file_buffer = self.buffers.get(view.buffer_id);
+        let gutter = gutter_width(file_buffer, &self.options);
+        let minimap = minimap_width(&self.options, area.width);
+        if position.x >= area.right() - minimap {
+            self.mouse_drag = Some(MouseDrag::Minimap);
+            self.scroll_to_minimap(area, position.y, false);
+        } else if position.x < area.x + gutter {
+            self.stop_visual();
+            self.move_to_cell(area, Position::new(area.x + gutter, position.y));
+            self.start_visual(true);
+            self.mouse_drag = Some(MouseDrag::Selection);
+        } else {
+            self.stop_visual();
+            self.move_to_cell(area, position);
+            if double_click {
+                self.select_word();
+            }
+            self.mouse_drag = Some(MouseDrag::Selection);
+        }
+    }
+
+    /// Handles a move of the mouse with the left button pressed.
+    fn drag(&mut self, position: Position) {
+        let views = &self.tabs.current().views;
+        let area = views_areas(views, content_area(self, self.area))[views.current];
+        match self.mouse_drag {
+            Some(MouseDrag::Selection) => {
+                if self.editor_mode != EditorMode::Visual {
+                    self.start_visual(false);
+                }
+                self.move_to_cell(area, position);
+            }
+            Some(MouseDrag::Separator(index)) => self.resize_views(index, position),
+            Some(MouseDrag::Minimap) => self.scroll_to_minimap(area, position.y, true),
+            Some(MouseDrag::Buffer(buffer_id)) => {
+                if let Some(bufferline_area) = bufferline_area(self, self.area)
+                    && let Some(target) = buffer_at(self, bufferline_area, position.x)
+                    && target != buffer_id
+                {
+                    self.buffers.reorder(buffer_id, target);
+                }
+            }
+            None => {}
+        }
+    }
+
+    /// Moves the cursor of the current view to the text shown at a cell, or the nearest one.
+    fn move_to_cell(&mut self, area: Rect, position: Position) {
+        let view = self.tabs.current_mut().views.current_mut();
+        let file_buffer = self.buffers.get(view.buffer_id);
+        let gutter = gutter_width(file_buffer, &self.options);
+        let minimap = minimap_width(&self.options, area.width);
+        let right = area.right().saturating_sub(minimap + 1);
+        let x = position
+            .x
+            .clamp(area.x + gutter, right.max(area.x + gutter));
+        let y = position.y.clamp(area.y, area.bottom().saturating_sub(1));
+        (view.current_line, view.current_column) =
+            view.position_at(file_buffer, &self.options, x - area.x - gutter, y - area.y);
+        view.clamp(file_buffer);
+        view.remember_column(file_buffer);
+    }
+
+    /// Selects the word under the cursor, as done by a double click.
+    fn select_word(&mut self) {
+        let view = self.tabs.current_mut().views.current_mut();
+        let text = self.buffers.get(view.buffer_id).line(view.current_line);
+        let is_word = |c: &char| c.is_alphanumeric() || *c == '_';
+        let column = view.current_column as usize;
+        if !text.get(column).is_some_and(is_word) {
+            return;
+        }
+        let start = text[..column]
+            .iter()
+            .rev()
+            .take_while(|c| is_word(c))
+            .count();
+        let end = text[column..].iter().take_while(|c| is_word(c)).count();
+        view.current_column = (column - start) as u16;
+        self.start_visual(false);
+        let view = self.tabs.current_mut().views.current_mut();
+        view.current_column = (column + end - 1) as u16;
+    }
+
+    /// Scrolls the current view to the lines shown at a row of its minimap. When dragged, the
+    /// rows cover the whole buffer so that the minimap doesn't move under the mouse.
+    fn scroll_to_minimap(&mut self, area: Rect, y: u16, dragged: bool) {
+        let view = self.tabs.current_mut().views.current_mut();
+        let file_buffer = self.buffers.get(view.buffer_id);
+        let lines_number = file_buffer.lines_number as usize;
+        let height = area.height as usize;
+        let row = y.clamp(area.y, area.bottom().saturating_sub(1)) - area.y;
+        let line = if dragged {
+            row as usize * lines_number / height.max(1)
+        } else {
+            first_line(lines_number, view.scroll_y as usize, height, height)
+                + row as usize * LINES_PER_ROW
+        };
+        let line = usize::min(line, lines_number.saturating_sub(1)) as u16;
+        view.scroll_y = line.saturating_sub(area.height / 2);
+        view.current_line = line;
+        view.restore_desired_column(file_buffer);
+    }
+
+    /// Moves the separator following a view to the mouse, both views keeping a cell at least.
+    fn resize_views(&mut self, index: usize, position: Position) {
+        let content_area = content_area(self, self.area);
+        let views = &mut self.tabs.current_mut().views;
+        let areas = views_areas(views, content_area);
+        let (mut sizes, start, pointer): (Vec<u16>, u16, u16) = match views.split {
+            Split::Horizontal => (
+                areas.iter().map(|area| area.height).collect(),
+                areas[index].y,
+                position.y,
+            ),
+            Split::Vertical => (
+                areas.iter().map(|area| area.width).collect(),
+                areas[index].x,
+                position.x,
+            ),
+        };
+        let total = sizes[index] + sizes[index + 1];
+        if total < 2 {
+            return;
+        }
+        sizes[index] = pointer.saturating_sub(start).clamp(1, total - 1);
+        sizes[index + 1] = total - sizes[index];
+        views.sizes = Some(sizes);
+    }
+
     fn set_cursor_type(cursor_type: CursorType) {
         let mut stdout = stdout();
         match cursor_type {
@@ -285,59 +1417,1052 @@ impl Editor {
     ///
     /// The tick event is where you can update the state of your application with any logic that
     /// needs to be updated at a fixed frame rate. E.g. polling a server, updating an animation.
-    pub fn tick(&self) {}
+    pub fn tick(&mut self) {
+        if self.messages.expire() {
+            self.dirty = true;
+        }
+        if self
+            .pending_since
+            .is_some_and(|since| since.elapsed() >= KEY_HINTS_DELAY)
+        {
+            self.pending_since = None;
+            self.open_key_hints_popup();
+            self.dirty = true;
+        }
+    }
 
-    fn exit_popup(&mut self) {
-        self.command_popup.running = false;
-        self.command_popup.input_field = EMPTY_STRING.to_string();
+    fn current_view_and_buffer(&mut self) -> (&mut View, &mut FileBuffer) {
+        let view = self.tabs.current_mut().views.current_mut();
+        let file_buffer = self.buffers.get_mut(view.buffer_id);
+        (view, file_buffer)
     }
 
-    fn auto_save(&mut self) {
-        let _guard = self.lock.lock().unwrap();
-        let current = Local::now();
-        let last_save_duration = current.naive_local() - self.last_save.naive_local();
-        if last_save_duration.num_seconds() < 1 {
+    /// Remembers the display column of the cursor of the current view.
+    fn remember_column(&mut self) {
+        let (view, file_buffer) = self.current_view_and_buffer();
+        view.remember_column(file_buffer);
+    }
+
+    /// Moves the cursor of the current view to a location, remembering where it jumped from.
+    fn jump_to(&mut self, location: Location) {
+        let view = self.tabs.current_mut().views.current_mut();
+        view.jumps.push(view.location());
+        view.context = Some(view.location());
+        view.go_to(location, self.buffers.get_mut(location.buffer_id));
+    }
+
+    /// Text covered by a motion from the cursor of the current view.
+    fn motion_range(&mut self, motion: Motion) -> Option<Range> {
+        let mark = match motion {
+            Motion::Mark(name, _) => Some(self.mark(name)?),
+            _ => None,
+        };
+        let (view, file_buffer) = self.current_view_and_buffer();
+        let last_line = file_buffer.lines_number.saturating_sub(1);
+        let mut target = view.clone();
+        let linewise = match motion {
+            Motion::Left => {
+                target.move_cursor(file_buffer, Move::Left);
+                false
+            }
+            Motion::Up => {
+                target.move_cursor(file_buffer, Move::Up);
+                true
+            }
+            Motion::Right => {
+                target.move_cursor(file_buffer, Move::Right);
+                false
+            }
+            Motion::Down => {
+                target.move_cursor(file_buffer, Move::Down);
+                true
+            }
+            Motion::NextWord => {
+                target.move_to_next_word(file_buffer);
+                false
+            }
+            Motion::PreviousWord => {
+                target.move_to_previous_word(file_buffer);
+                false
+            }
+            Motion::Line => true,
+            Motion::GoToLine(line) => {
+                let line = line.map_or(last_line, |line| line.saturating_sub(1));
+                target.current_line = u16::min(line, last_line);
+                true
+            }
+            Motion::Mark(_, exact) => {
+                let mark = mark?;
+                if mark.buffer_id != view.buffer_id {
+                    return None;
+                }
+                target.current_line = mark.line;
+                target.current_column = mark.column;
+                target.clamp(file_buffer);
+                !exact
+            }
+            Motion::MatchingBracket => {
+                let cursor = (view.current_line, view.current_column);
+                (target.current_line, target.current_column) =
+                    jump_target(&file_buffer.file, cursor, &file_buffer.options.filetype)?;
+                false
+            }
+        };
+        let mut range = Range::new(
+            (view.current_line, view.current_column),
+            (target.current_line, target.current_column),
+            linewise,
+        );
+        // `%` covers the bracket it ends on, whichever the direction
+        if motion == Motion::MatchingBracket {
+            let line = &file_buffer.file[range.end.0 as usize];
+            range.end.1 = next_boundary(line, range.end.1);
+        }
+        Some(range)
+    }
+
+    /// Applies an operator to the text covered by a motion.
+    fn operate(&mut self, operator: Operator, motion: Motion) {
+        if let Some(range) = self.motion_range(motion) {
+            self.operate_on(operator, range);
+        }
+    }
+
+    /// Text selected in visual mode, from its start to the cursor included.
+    pub fn selection(&self) -> Option<Range> {
+        if self.editor_mode != EditorMode::Visual {
+            return None;
+        }
+        let start = self.selection_start?;
+        let view = self.tabs.current().views.current();
+        if start.buffer_id != view.buffer_id {
+            return None;
+        }
+        let mut range = Range::new(
+            (start.line, start.column),
+            (view.current_line, view.current_column),
+            self.selection_linewise,
+        );
+        if !range.linewise {
+            let line = self.buffers.get(view.buffer_id).line(range.end.0);
+            range.end.1 = next_boundary(line, range.end.1);
+        }
+        Some(range)
+    }
+
+    /// Starts selecting text from the cursor of the current view.
+    fn start_visual(&mut self, linewise: bool) {
+        self.start_screen = None;
+        self.selection_start = Some(self.tabs.current().views.current().location());
+        self.selection_linewise = linewise;
+        self.editor_mode = EditorMode::Visual;
+        Self::set_cursor_type(CursorType::Block);
+    }
+
+    fn stop_visual(&mut self) {
+        if self.editor_mode == EditorMode::Visual {
+            self.editor_mode = EditorMode::Normal;
+        }
+        self.selection_start = None;
+    }
+
+    /// Applies an operator to the text of a range of the current buffer.
+    fn operate_on(&mut self, operator: Operator, range: Range) {
+        let (view, file_buffer) = self.current_view_and_buffer();
+        let buffer_id = view.buffer_id;
+        let text = if range.linewise {
+            file_buffer.file[range.start.0 as usize..=range.end.0 as usize].to_vec()
+        } else {
+            file_buffer.text(range.start, range.end)
+        };
+        self.registers.set(
+            self.selected_register.take(),
+            Register {
+                text,
+                linewise: range.linewise,
+            },
+        );
+        if operator != Operator::Yank {
+            self.delete_range(range, operator == Operator::Change);
+        }
+        let (view, file_buffer) = self.current_view_and_buffer();
+        let (line, column) = range.start;
+        for (name, (line, column)) in [('[', range.start), (']', range.end)] {
+            let location = Location {
+                buffer_id,
+                line,
+                column,
+            };
+            file_buffer.marks.insert(name, location);
+        }
+        view.current_line = line;
+        view.current_column = column;
+        view.clamp(file_buffer);
+        view.remember_column(file_buffer);
+        if operator == Operator::Change {
+            self.editor_mode = EditorMode::Insert;
+            Self::set_cursor_type(CursorType::Line);
+        }
+    }
+
+    /// Removes the text of a range from the current buffer. A linewise range leaves an empty line
+    /// when `keep_line` is set.
+    fn delete_range(&mut self, range: Range, keep_line: bool) {
+        let (_, file_buffer) = self.current_view_and_buffer();
+        let ((start_line, start_column), (end_line, end_column)) = (range.start, range.end);
+        if range.linewise {
+            let first_line = if keep_line {
+                start_line + 1
+            } else {
+                start_line
+            };
+            if end_line >= first_line {
+                let edit = file_buffer.delete_lines(first_line, end_line + 1 - first_line);
+                self.apply_edit(edit);
+            }
+            if keep_line {
+                let (_, file_buffer) = self.current_view_and_buffer();
+                let line_len = file_buffer.line_len(start_line);
+                let edit = file_buffer.delete_chars(start_line, 0, line_len);
+                self.apply_edit(edit);
+            }
+        } else if start_line == end_line {
+            let end_column = u16::min(end_column, file_buffer.line_len(end_line));
+            if end_column > start_column {
+                let edit =
+                    file_buffer.delete_chars(start_line, start_column, end_column - start_column);
+                self.apply_edit(edit);
+            }
+        } else {
+            let end_column = u16::min(end_column, file_buffer.line_len(end_line)) as usize;
+            let tail = file_buffer.line(end_line)[end_column..].to_vec();
+            let start_line_len = file_buffer.line_len(start_line);
+            let edit =
+                file_buffer.delete_chars(start_line, start_column, start_line_len - start_column);
+            self.apply_edit(edit);
+            let (_, file_buffer) = self.current_view_and_buffer();
+            let edit = file_buffer.insert_chars(start_line, start_column, &tail);
+            self.apply_edit(edit);
+            let (_, file_buffer) = self.current_view_and_buffer();
+            let edit = file_buffer.delete_lines(start_line + 1, end_line - start_line);
+            self.apply_edit(edit);
+        }
+    }
+
+    /// Puts the text of the selected register after the cursor, or before it.
+    fn paste(&mut self, before: bool) {
+        let Some(register) = self.registers.get(self.selected_register.take()).cloned() else {
             return;
         };
-        for (path, buf) in self.buffers.files.iter() {
-            if path == EMPTY_STRING {
+        let (view, file_buffer) = self.current_view_and_buffer();
+        let line = view.current_line;
+        if register.linewise {
+            let line = if before { line } else { line + 1 };
+            let edit = file_buffer.insert_lines(line, register.text);
+            self.apply_edit(edit);
+            let view = self.tabs.current_mut().views.current_mut();
+            view.current_line = line;
+            view.current_column = 0;
+            view.desired_column = 0;
+            return;
+        }
+        let column = if before {
+            view.current_column
+        } else {
+            next_boundary(file_buffer.line(line), view.current_column)
+        };
+        let tail = file_buffer.line(line)[column as usize..].to_vec();
+        let mut text = register.text;
+        if text.len() > 1 {
+            let edit = file_buffer.delete_chars(line, column, tail.len() as u16);
+            self.apply_edit(edit);
+            if let Some(last) = text.last_mut() {
+                last.extend(tail);
+            }
+        }
+        let first = text.remove(0);
+        let (_, file_buffer) = self.current_view_and_buffer();
+        let edit = file_buffer.insert_chars(line, column, &first);
+        self.apply_edit(edit);
+        if !text.is_empty() {
+            let (_, file_buffer) = self.current_view_and_buffer();
+            let edit = file_buffer.insert_lines(line + 1, text);
+            self.apply_edit(edit);
+        }
+        let (view, file_buffer) = self.current_view_and_buffer();
+        view.current_line = line;
+        view.current_column = column + (first.len() as u16).saturating_sub(1);
+        view.clamp(file_buffer);
+        view.remember_column(file_buffer);
+    }
+
+    /// Position of a mark. The file of a file mark saved by a previous run gets opened.
+    fn mark(&mut self, name: char) -> Option<Location> {
+        if name.is_ascii_uppercase()
+            && !self.global_marks.contains_key(&name)
+            && let Some(mark) = self.state.marks.get(&name.to_string()).cloned()
+        {
+            let location = Location {
+                buffer_id: self.open_buffer(&mark.file_path)?,
+                line: mark.line,
+                column: mark.column,
+            };
+            self.global_marks.insert(name, location);
+        }
+        let view = self.tabs.current().views.current();
+        match name {
+            'A'..='Z' => self.global_marks.get(&name).copied(),
+            '\'' | '`' => view.context,
+            _ => self.buffers.files[&view.buffer_id]
+                .marks
+                .get(&name)
+                .copied(),
+        }
+    }
+
+    /// Lines of the `:marks` listing.
+    fn marks_listing(&self) -> Vec<Vec<char>> {
+        let view = self.tabs.current().views.current();
+        let file_buffer = &self.buffers.files[&view.buffer_id];
+        let mut marks: Vec<(char, Location)> =
+            view.context.map(|mark| ('\'', mark)).into_iter().collect();
+        marks.extend(
+            file_buffer
+                .marks
+                .iter()
+                .filter(|(name, _)| name.is_ascii_lowercase())
+                .map(|(name, mark)| (*name, *mark)),
+        );
+        marks.extend(self.global_marks.iter().map(|(name, mark)| (*name, *mark)));
+        marks.extend(
+            file_buffer
+                .marks
+                .iter()
+                .filter(|(name, _)| !name.is_ascii_lowercase())
+                .map(|(name, mark)| (*name, *mark)),
+        );
+        let mut lines = vec!["mark line  col file/text".chars().collect()];
+        for (name, mark) in marks {
+            let Some(mark_buffer) = self.buffers.files.get(&mark.buffer_id) else {
                 continue;
             };
-            let file_bytes = to_bytes(&buf.file);
-            let bytes_slice: &[u8] = &file_bytes;
-            let Ok(mut file) = File::create(path) else {
+            let description = if mark.buffer_id == view.buffer_id {
+                mark_buffer
+                    .file
+                    .get(mark.line as usize)
+                    .map(|line| line.iter().collect::<String>().trim().to_string())
+                    .unwrap_or_default()
+            } else {
+                mark_buffer.name()
+            };
+            let line = format!(
+                " {}  {:>5} {:>4} {}",
+                name,
+                mark.line + 1,
+                mark.column,
+                description
+            );
+            lines.push(line.chars().collect());
+        }
+        lines
+    }
+
+    /// Displays a buffer in the current view, at its last known cursor position.
+    fn show_buffer(&mut self, buffer_id: BufferId) {
+        self.start_screen = None;
+        let current = self.tabs.current().views.current().location();
+        self.remember_position(current);
+        let position = self.buffers.files[&buffer_id]
+            .file_path
+            .as_ref()
+            .and_then(|file_path| self.state.positions.get(file_path));
+        self.jump_to(Location {
+            buffer_id,
+            line: position.map_or(0, |position| position.line),
+            column: position.map_or(0, |position| position.column),
+        });
+    }
+
+    /// Displays lines produced by the editor in a split below the current view. The listing
+    /// buffer of the previous call is reused, and focused when a view already shows it.
+    fn show_listing(&mut self, lines: Vec<Vec<char>>) {
+        let listing = FileBuffer::new(BufferKind::Listing, None, lines);
+        let buffer_id = match self
+            .listing_buffer
+            .filter(|buffer_id| self.buffers.files.contains_key(buffer_id))
+        {
+            Some(buffer_id) => {
+                let file_buffer = self.buffers.get_mut(buffer_id);
+                *file_buffer = listing;
+                for view in self.tabs.views_mut() {
+                    if view.buffer_id == buffer_id {
+                        view.clamp(file_buffer);
+                    }
+                }
+                buffer_id
+            }
+            None => self.buffers.create(listing),
+        };
+        self.listing_buffer = Some(buffer_id);
+        let views = &mut self.tabs.current_mut().views;
+        match views
+            .list
+            .iter()
+            .position(|view| view.buffer_id == buffer_id)
+        {
+            Some(index) => views.current = index,
+            None => views.split(Split::Horizontal),
+        }
+        self.show_buffer(buffer_id);
+    }
+
+    /// Displays the next or previous buffer of the bufferline in the current view.
+    fn cycle_buffer(&mut self, forward: bool) {
+        let listed = self.buffers.listed();
+        let current = self.tabs.current().views.current().buffer_id;
+        let Some(index) = listed.iter().position(|id| *id == current) else {
+            if let Some(buffer_id) = listed.first() {
+                self.show_buffer(*buffer_id);
+            }
+            return;
+        };
+        let index = if forward {
+            (index + 1) % listed.len()
+        } else {
+            (index + listed.len() - 1) % listed.len()
+        };
+        if listed[index] != current {
+            self.show_buffer(listed[index]);
+        }
+    }
+
+    /// Opens a file or a directory, remembering the file among the recent ones. A file that
+    /// can't be read is reported on the message line.
+    pub fn open_buffer(&mut self, file_path: &str) -> Option<BufferId> {
+        let buffer_id = match self.buffers.open(file_path) {
+            Ok(buffer_id) => buffer_id,
+            Err(e) => {
+                self.messages
+                    .error(format!("Can't open {}: {}", file_path, e));
+                return None;
+            }
+        };
+        if let Some(file_path) = self.buffers.files[&buffer_id].file_path.clone() {
+            self.remember_recent_file(&file_path);
+        }
+        Some(buffer_id)
+    }
+
+    fn remember_recent_file(&mut self, file_path: &str) {
+        if Path::new(file_path).is_file() {
+            self.state.recent_files.push(file_path);
+        }
+    }
+
+    /// Keeps only the buffers for which the predicate holds, forgetting the file marks and
+    /// selection pointing at the dropped ones. The views must not show them anymore.
+    pub fn retain_buffers(&mut self, keep: impl FnMut(BufferId, &FileBuffer) -> bool) {
+        self.buffers.retain(keep);
+        let files = &self.buffers.files;
+        self.global_marks
+            .retain(|_, mark| files.contains_key(&mark.buffer_id));
+        if self
+            .selection_start
+            .is_some_and(|start| !files.contains_key(&start.buffer_id))
+        {
+            self.stop_visual();
+        }
+    }
+
+    /// Remembers the cursor position in a file, restored when the file is displayed again.
+    fn remember_position(&mut self, location: Location) {
+        let Some(file_path) = self
+            .buffers
+            .files
+            .get(&location.buffer_id)
+            .and_then(|file_buffer| file_buffer.file_path.clone())
+        else {
+            return;
+        };
+        let position = StatePosition {
+            line: location.line,
+            column: location.column,
+            time: Local::now().timestamp(),
+        };
+        self.state.positions.insert(file_path, position);
+    }
+
+    /// Saves the histories, registers, file marks and cursor positions for the next runs.
+    fn save_state(&mut self) {
+        let time = Local::now().timestamp();
+        let locations: Vec<Location> = self.tabs.views_mut().map(|view| view.location()).collect();
+        for location in locations {
+            self.remember_position(location);
+        }
+        for (name, register) in &self.registers.registers {
+            let register = StateRegister {
+                text: register
+                    .text
+                    .iter()
+                    .map(|line| line.iter().collect())
+                    .collect(),
+                linewise: register.linewise,
+                time,
+            };
+            let known = self.state.registers.get(&name.to_string());
+            if known.is_none_or(|known| {
+                (&known.text, known.linewise) != (&register.text, register.linewise)
+            }) {
+                self.state.registers.insert(name.to_string(), register);
+            }
+        }
+        for (name, mark) in &self.global_marks {
+            let Some(file_path) = self
+                .buffers
+                .files
+                .get(&mark.buffer_id)
+                .and_then(|file_buffer| file_buffer.file_path.clone())
+            else {
                 continue;
             };
-            if let Err(e) = file.write_all(bytes_slice) {
-                log_error(&e.to_string());
+            let mark = StateMark {
+                file_path,
+                line: mark.line,
+                column: mark.column,
+                time,
+            };
+            let known = self.state.marks.get(&name.to_string());
+            if known.is_none_or(|known| {
+                (&known.file_path, known.line, known.column)
+                    != (&mark.file_path, mark.line, mark.column)
+            }) {
+                self.state.marks.insert(name.to_string(), mark);
             }
-            if let Err(e) = file.flush() {
-                log_error(&e.to_string());
+        }
+        if let Err(e) = self.state.save() {
+            self.messages.error(e.to_string());
+        }
+    }
+
+    /// Jumps to the next match of the last searched pattern.
+    fn search(&mut self, backward: bool) {
+        let pattern: Vec<char> = self.search_pattern.chars().collect();
+        let (view, file_buffer) = self.current_view_and_buffer();
+        let buffer_id = view.buffer_id;
+        let found =
+            file_buffer.find_pattern(&pattern, view.current_line, view.current_column, backward);
+        if let Some((line, column)) = found {
+            self.jump_to(Location {
+                buffer_id,
+                line,
+                column,
+            });
+        }
+    }
+
+    fn create_scratch_buffer(&mut self) -> BufferId {
+        self.buffers
+            .create(FileBuffer::new(BufferKind::Scratch, None, vec![]))
+    }
+
+    fn move_cursor(&mut self, move_option: Move) {
+        let (view, file_buffer) = self.current_view_and_buffer();
+        view.move_cursor(file_buffer, move_option);
+    }
+
+    /// Shows the marks set with `m` in the sign column of the current buffer.
+    fn place_mark_signs(&mut self) {
+        let style = self.theme.style(Group::MarkSign);
+        let (_, file_buffer) = self.current_view_and_buffer();
+        file_buffer.signs.clear(MARKS_NAMESPACE);
+        let signs: Vec<Sign> = file_buffer
+            .marks
+            .iter()
+            .filter(|(name, _)| name.is_ascii_lowercase())
+            .map(|(name, mark)| Sign {
+                namespace: MARKS_NAMESPACE,
+                line: mark.line,
+                text: name.to_string(),
+                style,
+                priority: 10,
+            })
+            .collect();
+        for sign in signs {
+            file_buffer.signs.place(sign);
+        }
+    }
+
+    /// Records an edit of the current file buffer in its change list, and keeps the positions
+    /// held by every view, in every tab page, following the text.
+    fn apply_edit(&mut self, edit: Edit) {
+        self.start_screen = None;
+        let buffer_id = self.tabs.current().views.current().buffer_id;
+        let file_buffer = self.buffers.get_mut(buffer_id);
+        let (line, column) = edit.position();
+        file_buffer.changes.apply_edit(buffer_id, edit);
+        let change = Location {
+            buffer_id,
+            line,
+            column,
+        };
+        file_buffer.changes.push_change(change);
+        file_buffer.modified = true;
+        file_buffer.signs.apply_edit(edit);
+        file_buffer.marks.retain(|_, mark| {
+            mark.apply_edit(buffer_id, edit) || !matches!(edit, Edit::RemoveLines { .. })
+        });
+        self.global_marks.retain(|_, mark| {
+            mark.apply_edit(buffer_id, edit) || !matches!(edit, Edit::RemoveLines { .. })
+        });
+        let (last_line, last_column) = edit.last_position();
+        file_buffer.marks.insert('.', change);
+        file_buffer.marks.insert('[', change);
+        file_buffer.marks.insert(
+            ']',
+            Location {
+                buffer_id,
+                line: last_line,
+                column: last_column,
+            },
+        );
+        for view in self.tabs.views_mut() {
+            view.apply_edit(buffer_id, edit, file_buffer);
+        }
+    }
+
+    /// Lays out the views of every tab page again for the new terminal size, scrolling them to
+    /// keep their cursor visible.
+    fn resize(&mut self, width: u16, height: u16) {
+        let content_area = content_area(self, Rect::new(0, 0, width, height));
+        for tab_page in self.tabs.list.iter_mut() {
+            let areas = views_areas(&tab_page.views, content_area);
+            for (view, area) in tab_page.views.list.iter_mut().zip(areas) {
+                view.scroll_to_cursor(area, self.buffers.get(view.buffer_id), &self.options);
+            }
+        }
+    }
+
+    /// Scrolls every view to its cursor and returns the terminal position of the focused cursor.
+    fn update_viewports(&mut self, area: Rect) -> Position {
+        self.area = area;
+        let areas = views_areas(&self.tabs.current().views, content_area(self, area));
+        let views = &mut self.tabs.current_mut().views;
+        for (view, area) in views.list.iter_mut().zip(areas.iter()) {
+            let file_buffer = self.buffers.get_mut(view.buffer_id);
+            view.scroll_to_cursor(*area, file_buffer, &self.options);
+        }
+        let view = views.current();
+        let file_buffer = self.buffers.get_mut(view.buffer_id);
+        self.cursor_position =
+            view.to_cursor_position(areas[views.current], file_buffer, &self.options);
+        self.cursor_position
+    }
+
+    fn restore_session(&mut self, session_path: &Path) {
+        match Session::load(session_path) {
+            Ok(session) => {
+                session.restore(self);
+                self.state
+                    .recent_sessions
+                    .push(&session_path.to_string_lossy());
             }
+            Err(e) => self.messages.error(e.to_string()),
+        }
+    }
+
+    fn exit_popup(&mut self) {
+        self.popups.close_focused();
+    }
+
+    /// Opens a popup over the others, listing the keys that can follow the pending ones.
+    fn open_key_hints_popup(&mut self) {
+        let hints = key_hints(&self.pending_keys);
+        if hints.is_empty() {
+            return;
         }
+        let width = Size::Percent(60);
+        let inner_width = width.cells(self.area.width).saturating_sub(2);
+        let lines = key_hints_lines(&hints, inner_width, self.theme.style(Group::Special));
+        let popup = Popup {
+            focusable: false,
+            z_index: 1,
+            ..Popup::new(
+                format!(" {} ", self.pending_keys),
+                Anchor::Editor,
+                width,
+                Size::Cells(lines.len() as u16 + 2),
+                PopupContent::Text(lines),
+            )
+        };
+        self.key_hints_popup = Some(self.popups.open(popup));
+    }
+
+    fn auto_save(&mut self) {
+        let _guard = self.lock.lock().unwrap();
+        let current = Local::now();
+        let last_save_duration = current.naive_local() - self.last_save.naive_local();
+        if last_save_duration.num_seconds() < 1 {
+            return;
+        };
+        write_buffers(&mut self.buffers, &mut self.messages);
         self.last_save = Local::now();
     }
 }
 
-fn to_bytes(buffer: &Vec<Vec<char>>) -> Vec<u8> {
+/// Writes the buffers to their file.
+fn write_buffers(buffers: &mut FilesBuffers, messages: &mut Messages) {
+    for buf in buffers.files.values_mut() {
+        let Some(path) = buf.file_path.as_ref().filter(|_| buf.can_save()) else {
+            continue;
+        };
+        let file_bytes = to_bytes(&buf.file, buf.options.line_ending());
+        let result = File::create(path)
+            .and_then(|mut file| file.write_all(&file_bytes).and_then(|()| file.flush()));
+        if let Err(e) = result {
+            messages.error(format!("Can't write {}: {}", path, e));
+            continue;
+        }
+        buf.modified = false;
+    }
+}
+
+fn to_bytes(buffer: &Vec<Vec<char>>, line_ending: &str) -> Vec<u8> {
     let mut result = String::new();
 
     for line in buffer {
         for c in line {
             result.push(*c);
         }
-        result.push('\n'); // Optional: join lines with newlines
+        result.push_str(line_ending);
     }
 
     result.into_bytes() // returns Vec<u8>
 }
 
-fn log_error(msg: &str) {
-    let mut file = OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open("error.log")
-        .unwrap();
+/// Auto-session of the current directory.
+fn current_autosession_path() -> Option<PathBuf> {
+    let directory = env::current_dir().ok()?;
+    autosession_path(&directory.to_string_lossy())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    writeln!(file, "{}", msg).unwrap();
+    /// Presses the keys, then handles the app events they queued.
+    fn press(editor: &mut Editor, keys: &str) {
+        for key in keys.chars() {
+            editor
+                .handle_terminal_key(KeyEvent::from(KeyCode::Char(key)))
+                .unwrap();
+            while let Some(Event::App(app_event)) = editor.events.try_next() {
+                editor.handle_app_event(app_event);
+            }
+        }
+    }
+
+    #[test]
+    fn selection_should_cover_the_text_between_its_start_and_the_cursor() {
+        let lines = ["one two", "three", "four"];
+        let inputs = [
+            ((0, 0), false, "ll"),
+            ((1, 2), false, "kh"),
+            ((0, 3), true, "j"),
+            ((2, 1), true, "k"),
+        ];
+        let expect = [
+            ((0, 0), (0, 3), false),
+            ((0, 1), (1, 3), false),
+            ((0, 0), (1, 0), true),
+            ((1, 0), (2, 0), true),
+        ];
+        for ((position, linewise, keys), expect) in inputs.into_iter().zip(expect) {
+            let mut editor = Editor::with_lines(&lines);
+            let view = editor.tabs.current_mut().views.current_mut();
+            (view.current_line, view.current_column) = position;
+            view.desired_column = position.1;
+            editor.start_visual(linewise);
+            press(&mut editor, keys);
+            let selection = editor.selection().unwrap();
+            assert_eq!(
+                (selection.start, selection.end, selection.linewise),
+                expect,
+                "{}",
+                keys
+            );
+        }
+
+        // Esc leaves the selection made with the mouse
+        let mut editor = Editor::with_lines(&lines);
+        editor.start_visual(false);
+        press(&mut editor, "ll");
+        editor
+            .handle_terminal_key(KeyEvent::from(KeyCode::Esc))
+            .unwrap();
+        while let Some(Event::App(app_event)) = editor.events.try_next() {
+            editor.handle_app_event(app_event);
+        }
+        assert_eq!(editor.selection(), None);
+        assert_eq!(editor.editor_mode, EditorMode::Normal);
+        assert!(editor.running);
+    }
+
+    #[test]
+    fn listings_should_reuse_their_buffer() {
+        let mut editor = Editor::with_lines(&["text"]);
+        editor.execute_command(Command::Marks);
+        editor.messages.info("first");
+        editor.messages.info("second");
+        editor.execute_command(Command::Messages);
+        let views = &editor.tabs.current().views;
+        assert_eq!(editor.buffers.files.len(), 2);
+        assert_eq!(views.list.len(), 2);
+        let file_buffer = editor.buffers.get(views.current().buffer_id);
+        assert_eq!(file_buffer.kind, BufferKind::Listing);
+        assert_eq!(file_buffer.lines_number, 2);
+
+        editor.execute_command(Command::Close);
+        editor.execute_command(Command::Marks);
+        assert_eq!(editor.buffers.files.len(), 2);
+        assert_eq!(editor.tabs.current().views.list.len(), 2);
+    }
+
+    #[test]
+    fn quit_should_ask_once_before_dropping_changes() {
+        let mut editor = Editor::with_lines(&["text"]);
+        editor.buffers.get_mut(0).modified = true;
+        let dialogs = |editor: &Editor| {
+            editor
+                .popups
+                .iter()
+                .filter(|popup| matches!(popup.content, PopupContent::Confirm(_)))
+                .count()
+        };
+
+        // As with `2<Esc>`
+        editor.handle_app_event(AppEvent::Quit);
+        editor.handle_app_event(AppEvent::Quit);
+        assert_eq!(dialogs(&editor), 1);
+        press(&mut editor, "x");
+        assert_eq!(dialogs(&editor), 1);
+        press(&mut editor, "c");
+        assert_eq!(dialogs(&editor), 0);
+        assert!(editor.running);
+
+        // The scratch buffer can't be written, so the editor stays open
+        editor.handle_app_event(AppEvent::Quit);
+        press(&mut editor, "y");
+        assert_eq!(dialogs(&editor), 0);
+        assert!(editor.running);
+
+        editor.handle_app_event(AppEvent::Quit);
+        press(&mut editor, "n");
+        assert!(!editor.running);
+    }
+
+    #[test]
+    fn key_hints_should_only_list_keys_the_dispatcher_handles() {
+        let prefixes = [
+            "<C-w>", "g", "z", "m", "'", "`", "\"", "d", "y", "c", "dg", "d'", "d`",
+        ];
+        for prefix in prefixes {
+            let hints = key_hints(prefix);
+            assert!(!hints.is_empty(), "{}", prefix);
+            for (keys, _) in hints {
+                for keys in keys.split(' ') {
+                    let keys = match keys {
+                        "a-z" => "a",
+                        "A-Z" => "A",
+                        keys => keys,
+                    };
+                    let mut editor = Editor::with_lines(&["(text)", "text"]);
+                    editor.pending_keys = prefix.to_string();
+                    for key in keys.chars() {
+                        editor
+                            .handle_key_events(KeyEvent::from(KeyCode::Char(key)))
+                            .unwrap();
+                    }
+                    // Keys starting a longer sequence have their own hints, checked too
+                    let handled = editor.events.try_next().is_some()
+                        || editor.selected_register.is_some()
+                        || editor.pending_keys.len() > prefix.len();
+                    assert!(handled, "{}{}", prefix, keys);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn percent_should_go_to_a_share_of_the_buffer() {
+        let lines: Vec<String> = (0..10).map(|line| format!("({})", line)).collect();
+        let lines: Vec<&str> = lines.iter().map(String::as_str).collect();
+        let inputs = ["50%", "100%", "1%", "0%", "101%", "99999999999%", "%"];
+        let expect = [(4, 0), (9, 0), (0, 0), (0, 0), (2, 1), (2, 1), (2, 0)];
+        for (keys, expect) in inputs.into_iter().zip(expect) {
+            let mut editor = Editor::with_lines(&lines);
+            let view = editor.tabs.current_mut().views.current_mut();
+            (view.current_line, view.current_column) = (2, 1);
+            press(&mut editor, keys);
+            let view = editor.tabs.current().views.current();
+            assert_eq!((view.current_line, view.current_column), expect, "{}", keys);
+        }
+    }
+
+    #[test]
+    fn to_bytes_should_end_lines_with_the_file_format_separator() {
+        let lines: Vec<Vec<char>> = vec!["a".chars().collect(), vec![], "b c".chars().collect()];
+        assert_eq!(to_bytes(&lines, "\n"), b"a\n\nb c\n");
+        assert_eq!(to_bytes(&lines, "\r\n"), b"a\r\n\r\nb c\r\n");
+    }
+
+    #[test]
+    fn registers_should_paste_what_was_yanked_into_them() {
+        let mut editor = Editor::with_lines(&["one", "two"]);
+        press(&mut editor, "\"ayyjyy\"apP");
+        let lines: Vec<String> = editor
+            .buffers
+            .get(0)
+            .file
+            .iter()
+            .map(String::from_iter)
+            .collect();
+        assert_eq!(lines, ["one", "two", "two", "one"]);
+    }
+
+    #[test]
+    fn operators_should_apply_to_the_text_of_their_motion() {
+        let inputs = ["dw", "dj", "jdd", "ggjmakd'a", "llmabd`a", "cwx"];
+        let expect = [
+            vec![" two", "three four"],
+            vec![""],
+            vec!["one two"],
+            vec![""],
+            vec!["e two", "three four"],
+            vec!["x two", "three four"],
+        ];
+        for (keys, expect) in inputs.into_iter().zip(expect) {
+            let mut editor = Editor::with_lines(&["one two", "three four"]);
+            press(&mut editor, keys);
+            let lines: Vec<String> = editor
+                .buffers
+                .get(0)
+                .file
+                .iter()
+                .map(String::from_iter)
+                .collect();
+            assert_eq!(lines, expect, "{}", keys);
+        }
+    }
+
+    #[test]
+    fn percent_should_delete_up_to_the_matching_bracket() {
+        let inputs = [(0, 0), (0, 5), (0, 8), (1, 0)];
+        let expect = [
+            [";", "x"],
+            ["f(a, );", "x"],
+            ["f;", "x"],
+            ["f(a, (b));", "x"],
+        ];
+        for (cursor, expect) in inputs.into_iter().zip(expect) {
+            let mut editor = Editor::with_lines(&["f(a, (b));", "x"]);
+            let view = editor.tabs.current_mut().views.current_mut();
+            (view.current_line, view.current_column) = cursor;
+            press(&mut editor, "d%");
+            let lines: Vec<String> = editor
+                .buffers
+                .get(0)
+                .file
+                .iter()
+                .map(String::from_iter)
+                .collect();
+            assert_eq!(lines, expect, "{:?}", cursor);
+        }
+    }
+
+    #[test]
+    fn marks_should_follow_the_lines_they_are_set_on() {
+        let mut editor = Editor::with_lines(&["one", "two", "three"]);
+        press(&mut editor, "jjmagg");
+        editor.handle_app_event(AppEvent::CreateLine);
+        let mark = |editor: &Editor| editor.buffers.get(0).marks.get(&'a').map(|mark| mark.line);
+        assert_eq!(mark(&editor), Some(3));
+        let edit = editor.buffers.get_mut(0).delete_line(3);
+        editor.apply_edit(edit);
+        assert_eq!(mark(&editor), None);
+    }
+
+    #[test]
+    fn resize_should_keep_the_cursor_of_every_tab_page_visible() {
+        let lines: Vec<String> = (0..100).map(|line| line.to_string()).collect();
+        let lines: Vec<&str> = lines.iter().map(String::as_str).collect();
+        let mut editor = Editor::with_lines(&lines);
+        editor.tabs.current_mut().views.current_mut().current_line = 50;
+        editor.tabs.open(TabPage::new(View::new(0)));
+        editor.tabs.current_mut().views.current_mut().current_line = 80;
+        editor.tabs.previous();
+
+        editor.resize(80, 10);
+        let content_area = content_area(&editor, Rect::new(0, 0, 80, 10));
+        for tab_page in &editor.tabs.list {
+            let view = tab_page.views.current();
+            let height = views_areas(&tab_page.views, content_area)[0].height;
+            assert!(height > 0);
+            assert!(
+                (view.scroll_y..view.scroll_y + height).contains(&view.current_line),
+                "line {} scrolled to {}",
+                view.current_line,
+                view.scroll_y
+            );
+        }
+    }
+
+    #[test]
+    fn resize_views_should_keep_a_cell_for_each_view() {
+        let mut editor = Editor::with_lines(&["text"]);
+        editor.area = Rect::new(0, 0, 80, 24);
+        editor.tabs.current_mut().views.split(Split::Vertical);
+        let sizes = |editor: &Editor| editor.tabs.current().views.sizes.clone().unwrap();
+
+        editor.resize_views(0, Position::new(30, 5));
+        let total: u16 = sizes(&editor).iter().sum();
+        assert_eq!(sizes(&editor)[0], 30);
+        editor.resize_views(0, Position::new(0, 5));
+        assert_eq!(sizes(&editor), [1, total - 1]);
+        editor.resize_views(0, Position::new(200, 5));
+        assert_eq!(sizes(&editor), [total - 1, 1]);
+    }
+
+    #[test]
+    fn separator_at_should_only_hit_the_cells_between_views() {
+        let mut editor = Editor::with_lines(&["text"]);
+        editor.area = Rect::new(0, 0, 80, 24);
+        editor.tabs.current_mut().views.split(Split::Vertical);
+        let areas = views_areas(
+            &editor.tabs.current().views,
+            content_area(&editor, editor.area),
+        );
+        let (x, bottom) = (areas[0].right(), areas[0].bottom());
+        assert_eq!(editor.separator_at(Position::new(x, 0)), Some(0));
+        assert_eq!(editor.separator_at(Position::new(x, bottom - 1)), Some(0));
+        assert_eq!(editor.separator_at(Position::new(x, bottom)), None);
+        assert_eq!(editor.separator_at(Position::new(x - 1, 0)), None);
+    }
+
+    #[test]
+    fn select_word_should_select_the_word_under_the_cursor() {
+        let columns = [6, 4, 10, 12];
+        let expect = [Some((4, 10)), Some((4, 10)), Some((4, 10)), None];
+        for (column, expect) in columns.into_iter().zip(expect) {
+            let mut editor = Editor::with_lines(&["let foo_bar = 1;"]);
+            editor.tabs.current_mut().views.current_mut().current_column = column;
+            editor.select_word();
+            let selection = editor.selection().map(|range| (range.start.1, range.end.1));
+            assert_eq!(
+                selection,
+                expect.map(|(start, end)| (start, end + 1)),
+                "{}",
+                column
+            );
+        }
+    }
 }
@@ -0,0 +1,305 @@
+use std::ops::Range;
+
+/// Number of lines looked back to tell whether a line starts inside a block comment, and
+/// number of lines scanned at once when searching a match.
+const CHUNK_LINES: usize = 256;
+
+/// Comment and string delimiters of a language, the brackets between them being skipped.
+struct Syntax {
+    line_comment: Option<&'static str>,
+    block_comment: Option<(&'static str, &'static str)>,
+    quotes: &'static [char],
+    /// Are single quotes only used around one character, as in `'('`?
+    char_literals: bool,
+}
+
+fn syntax(filetype: &str) -> Option<Syntax> {
+    let c_like = |quotes| Syntax {
+        line_comment: Some("//"),
+        block_comment: Some(("/*", "*/")),
+        quotes,
+        char_literals: false,
+    };
+    let script = Syntax {
+        line_comment: Some("#"),
+        block_comment: None,
+        quotes: &['"', '\''],
+        char_literals: false,
+    };
+    match filetype {
+        "rust" | "c" | "cpp" | "java" => Some(Syntax {
+            char_literals: true,
+            ..c_like(&['"'])
+        }),
+        "go" => Some(Syntax {
+            char_literals: true,
+            ..c_like(&['"', '`'])
+        }),
+        "javascript" | "typescript" => Some(c_like(&['"', '\'', '`'])),
+        "css" => Some(Syntax {
+            line_comment: None,
+            ..c_like(&['"', '\''])
+        }),
+        "python" | "sh" | "toml" | "yaml" => Some(script),
+        "json" => Some(Syntax {
+            line_comment: None,
+            block_comment: None,
+            quotes: &['"'],
+            char_literals: false,
+        }),
+        "html" => Some(Syntax {
+            line_comment: None,
+            block_comment: Some(("<!--", "-->")),
+            quotes: &['"', '\''],
+            char_literals: false,
+        }),
+        _ => None,
+    }
+}
+
+/// Bracket closing or opening the given one, and whether it is an opening one.
+fn pair(bracket: char) -> Option<(char, bool)> {
+    match bracket {
+        '(' => Some((')', true)),
+        '[' => Some((']', true)),
+        '{' => Some(('}', true)),
+        ')' => Some(('(', false)),
+        ']' => Some(('[', false)),
+        '}' => Some(('{', false)),
+        _ => None,
+    }
+}
+
+fn starts_with(line: &[char], column: usize, delimiter: &str) -> bool {
+    delimiter
+        .chars()
+        .enumerate()
+        .all(|(offset, c)| line.get(column + offset) == Some(&c))
+}
+
+fn find(line: &[char], from: usize, delimiter: &str) -> Option<usize> {
+    (from..line.len()).find(|column| starts_with(line, *column, delimiter))
+}
+
+/// Columns of the brackets of a line outside of strings and comments. The comment state is
+/// updated to the one at the end of the line.
+fn code_brackets(line: &[char], syntax: Option<&Syntax>, in_comment: &mut bool) -> Vec<usize> {
+    let Some(syntax) = syntax else {
+        return (0..line.len())
+            .filter(|c| pair(line[*c]).is_some())
+            .collect();
+    };
+    let mut brackets = Vec::new();
+    let mut column = 0;
+    while column < line.len() {
+        if *in_comment {
+            let (_, end) = syntax.block_comment.unwrap_or_default();
+            match find(line, column, end) {
+                Some(end_column) => {
+                    *in_comment = false;
+                    column = end_column + end.chars().count();
+                }
+                None => break,
+            }
+            continue;
+        }
+        let c = line[column];
+        if syntax
+            .line_comment
+            .is_some_and(|start| starts_with(line, column, start))
+        {
+            break;
+        }
+        if let Some((start, _)) = syntax.block_comment
+            && starts_with(line, column, start)
+        {
+            *in_comment = true;
+            column += start.chars().count();
+            continue;
+        }
+        if c == '\'' && syntax.char_literals {
+            // Skips `'x'` and `'\x'`, but not the quote of a Rust lifetime
+            let end = match line.get(column + 1) {
+                Some('\\') => line[column + 2..]
+                    .iter()
+                    .position(|c| *c == '\'')
+                    .map(|end| column + 2 + end),
+                Some(_) if line.get(column + 2) == Some(&'\'') => Some(column + 2),
+                _ => None,
+            };
+            column = end.unwrap_or(column) + 1;
+            continue;
+        }
+        if syntax.quotes.contains(&c) {
+            let mut end = column + 1;
+            while end < line.len() && line[end] != c {
+                end += if line[end] == '\\' { 2 } else { 1 };
+            }
+            column = end + 1;
+            continue;
+        }
+        if pair(c).is_some() {
+            brackets.push(column);
+        }
+        column += 1;
+    }
+    brackets
+}
+
+/// Does the line start inside a block comment? Only the preceding [`CHUNK_LINES`] lines are
+/// looked at, for the last comment delimiter.
+fn starts_in_comment(lines: &[Vec<char>], line: usize, syntax: Option<&Syntax>) -> bool {
+    let Some((start, end)) = syntax.and_then(|syntax| syntax.block_comment) else {
+        return false;
+    };
+    for text in lines[line.saturating_sub(CHUNK_LINES)..line].iter().rev() {
+        let text: String = text.iter().collect();
+        match (text.rfind(start), text.rfind(end)) {
+            (Some(start), Some(end)) => return start > end,
+            (Some(_), None) => return true,
+            (None, Some(_)) => return false,
+            (None, None) => {}
+        }
+    }
+    false
+}
+
+/// Code brackets of each of the given lines.
+fn lines_brackets(
+    lines: &[Vec<char>],
+    range: Range<usize>,
+    syntax: Option<&Syntax>,
+) -> Vec<Vec<usize>> {
+    let mut in_comment = starts_in_comment(lines, range.start, syntax);
+    lines[range]
+        .iter()
+        .map(|line| code_brackets(line, syntax, &mut in_comment))
+        .collect()
+}
+
+/// Position of the bracket matching the one at the given position, searched within the bounds.
+fn find_match(
+    lines: &[Vec<char>],
+    syntax: Option<&Syntax>,
+    (line, column): (usize, usize),
+    bounds: Range<usize>,
+) -> Option<(u16, u16)> {
+    let bracket = lines[line][column];
+    let (matching, forward) = pair(bracket)?;
+    let mut depth = 0;
+    let mut visit = |index: usize, brackets: &[usize]| {
+        let columns: Box<dyn Iterator<Item = &usize>> = if forward {
+            Box::new(brackets.iter().filter(|c| index > line || **c > column))
+        } else {
+            Box::new(
+                brackets
+                    .iter()
+                    .rev()
+                    .filter(|c| index < line || **c < column),
+            )
+        };
+        for candidate in columns {
+            match lines[index][*candidate] {
+                c if c == bracket => depth += 1,
+                c if c == matching && depth == 0 => return Some((index as u16, *candidate as u16)),
+                c if c == matching => depth -= 1,
+                _ => {}
+            }
+        }
+        None
+    };
+    if forward {
+        let mut start = line;
+        while start < bounds.end {
+            let end = usize::min(start + CHUNK_LINES, bounds.end);
+            for (offset, brackets) in lines_brackets(lines, start..end, syntax).iter().enumerate() {
+                if let Some(position) = visit(start + offset, brackets) {
+                    return Some(position);
+                }
+            }
+            start = end;
+        }
+    } else {
+        let mut end = line + 1;
+        while end > bounds.start {
+            let start = usize::max(end.saturating_sub(CHUNK_LINES), bounds.start);
+            let chunk = lines_brackets(lines, start..end, syntax);
+            for (offset, brackets) in chunk.iter().enumerate().rev() {
+                if let Some(position) = visit(start + offset, brackets) {
+                    return Some(position);
+                }
+            }
+            end = start;
+        }
+    }
+    None
+}
+
+/// Target of `%`: the bracket matching the first one at or after the cursor on its line.
+pub fn jump_target(
+    lines: &[Vec<char>],
+    (line, column): (u16, u16),
+    filetype: &str,
+) -> Option<(u16, u16)> {
+    let syntax = syntax(filetype);
+    let (line, column) = (line as usize, column as usize);
+    let brackets = lines_brackets(lines, line..line + 1, syntax.as_ref()).pop()?;
+    let start = *brackets.iter().find(|c| **c >= column)?;
+    find_match(lines, syntax.as_ref(), (line, start), 0..lines.len())
+}
+
+/// Bracket under the cursor, or else just before it, and its match within the bounds.
+pub fn matching_pair(
+    lines: &[Vec<char>],
+    (line, column): (u16, u16),
+    filetype: &str,
+    bounds: Range<usize>,
+) -> Option<[(u16, u16); 2]> {
+    let syntax = syntax(filetype);
+    let (line, column) = (line as usize, column as usize);
+    let brackets = lines_brackets(lines, line..line + 1, syntax.as_ref()).pop()?;
+    let start = [Some(column), column.checked_sub(1)]
+        .into_iter()
+        .flatten()
+        .find(|c| brackets.contains(c))?;
+    let matching = find_match(lines, syntax.as_ref(), (line, start), bounds)?;
+    Some([(line as u16, start as u16), matching])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn brackets_should_match_outside_of_strings_and_comments() {
+        let text = [
+            "fn f(c: char) {",
+            "    let s = \"(\"; // )",
+            "    /* } */ if c == '{' { g(&'a) }",
+            "}",
+        ];
+        let lines: Vec<Vec<char>> = text.iter().map(|line| line.chars().collect()).collect();
+        let cases = [
+            ((0, 0), "rust", Some((0, 12))),
+            ((0, 13), "rust", Some((3, 0))),
+            ((3, 0), "rust", Some((0, 14))),
+            ((2, 24), "rust", Some((2, 33))),
+            ((2, 30), "rust", Some((2, 27))),
+            ((0, 13), "", Some((2, 7))),
+            ((3, 1), "rust", None),
+        ];
+        for (position, filetype, expected) in cases {
+            assert_eq!(
+                jump_target(&lines, position, filetype),
+                expected,
+                "{:?} {}",
+                position,
+                filetype
+            );
+        }
+        assert_eq!(
+            matching_pair(&lines, (0, 5), "rust", 0..4),
+            Some([(0, 4), (0, 12)])
+        );
+    }
+}
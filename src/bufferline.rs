@@ -0,0 +1,120 @@
+use std::path::Path;
+
+use crate::filesbuffers::FileBuffer;
+
+/// Marker shown on both ends of the bufferline when some buffers don't fit in it.
+pub const OVERFLOW_LEFT: &str = "<";
+pub const OVERFLOW_RIGHT: &str = ">";
+
+/// Short names telling the buffers apart: their file name, preceded by as many parent
+/// directories as needed when several files share it.
+pub fn unique_names(file_buffers: &[&FileBuffer]) -> Vec<String> {
+    let components: Vec<Vec<String>> = file_buffers
+        .iter()
+        .map(|file_buffer| {
+            let path = file_buffer.file_path.as_deref().unwrap_or_default();
+            Path::new(path)
+                .iter()
+                .rev()
+                .map(|component| component.to_string_lossy().to_string())
+                .filter(|component| component != "/")
+                .collect()
+        })
+        .collect();
+    let mut depths = vec![1; file_buffers.len()];
+    loop {
+        let names: Vec<String> = file_buffers
+            .iter()
+            .zip(&components)
+            .zip(&depths)
+            .map(|((file_buffer, components), depth)| {
+                if components.is_empty() {
+                    return file_buffer.name();
+                }
+                let depth = usize::min(*depth, components.len());
+                let mut name: Vec<&str> = components[..depth].iter().map(String::as_str).collect();
+                name.reverse();
+                name.join("/")
+            })
+            .collect();
+        let mut deepened = false;
+        for (index, name) in names.iter().enumerate() {
+            let shared = names
+                .iter()
+                .enumerate()
+                .any(|(other, other_name)| other != index && other_name == name);
+            if shared && depths[index] < components[index].len() {
+                depths[index] += 1;
+                deepened = true;
+            }
+        }
+        if !deepened {
+            return names;
+        }
+    }
+}
+
+/// Label of a buffer in the bufferline.
+pub fn label(name: &str, modified: bool) -> String {
+    if modified {
+        format!(" {} [+] ", name)
+    } else {
+        format!(" {} ", name)
+    }
+}
+
+/// Labels shown in a bufferline of the given width, with their offset from its start. When they
+/// don't all fit, they are scrolled to show the current one, and a cell is kept on both ends for
+/// the overflow markers.
+pub fn layout(widths: &[u16], current: usize, width: u16) -> Vec<(usize, u16)> {
+    let total: u16 = widths.iter().sum();
+    let (first, start, available) = if total <= width {
+        (0, 0, width)
+    } else {
+        let available = width.saturating_sub(2);
+        let mut first = usize::min(current, widths.len().saturating_sub(1));
+        let mut used = widths.get(first).copied().unwrap_or(0);
+        while first > 0 && used + widths[first - 1] <= available {
+            first -= 1;
+            used += widths[first];
+        }
+        (first, 1, available)
+    };
+    let mut labels = Vec::new();
+    let mut x = 0;
+    for (index, label_width) in widths.iter().enumerate().skip(first) {
+        if x + label_width > available && !labels.is_empty() {
+            break;
+        }
+        labels.push((index, start + x));
+        x += label_width;
+    }
+    labels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filesbuffers::BufferKind;
+
+    #[test]
+    fn bufferline_should_name_buffers_apart_and_scroll_to_the_current_one() {
+        let file = |path: &str| FileBuffer::new(BufferKind::File, Some(path.to_string()), vec![]);
+        let buffers = [
+            file("/src/app/mod.rs"),
+            file("/src/ui/mod.rs"),
+            file("/src/main.rs"),
+            FileBuffer::new(BufferKind::Scratch, None, vec![]),
+        ];
+        let buffers: Vec<&FileBuffer> = buffers.iter().collect();
+        assert_eq!(
+            unique_names(&buffers),
+            ["app/mod.rs", "ui/mod.rs", "main.rs", "[Scratch]"]
+        );
+
+        assert_eq!(layout(&[4, 4, 4], 2, 12), [(0, 0), (1, 4), (2, 8)]);
+        assert_eq!(layout(&[4, 4, 4], 0, 10), [(0, 1), (1, 5)]);
+        assert_eq!(layout(&[4, 4, 4], 2, 10), [(1, 1), (2, 5)]);
+        assert_eq!(layout(&[20, 4], 0, 10), [(0, 1)]);
+    }
+}
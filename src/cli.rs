@@ -1,4 +1,4 @@
-use clap::{Parser, command};
+use clap::Parser;
 
 const ABOUT: &str = "TODO";
 
@@ -12,6 +12,9 @@ pub struct CliOpt {
     #[arg(short = 'V', long = "version", help = "Print version")]
     version: bool,
 
+    #[arg(short = 'S', long = "session", help = "Restore session from file")]
+    session: Option<String>,
+
     #[arg(help = "Edit file(s)")]
     file: Vec<String>,
 }
@@ -26,6 +29,11 @@ impl CliOpt {
     pub fn version(&self) -> bool {
         self.version
     }
+
+    /// Session file to restore.
+    pub fn session(&self) -> Option<&String> {
+        self.session.as_ref()
+    }
 }
 
 #[cfg(test)]
@@ -38,20 +46,33 @@ mod tests {
             vec!["oxide".to_string()],
             vec!["oxide".to_string(), "--version".to_string()],
             vec!["oxide".to_string(), "README.md".to_string()],
+            vec![
+                "oxide".to_string(),
+                "-S".to_string(),
+                "Session.toml".to_string(),
+            ],
         ];
 
         let expect = [
             CliOpt {
                 file: vec![],
                 version: false,
+                session: None,
             },
             CliOpt {
                 file: vec![],
                 version: true,
+                session: None,
             },
             CliOpt {
                 file: vec!["README.md".to_string()],
                 version: false,
+                session: None,
+            },
+            CliOpt {
+                file: vec![],
+                version: false,
+                session: Some("Session.toml".to_string()),
             },
         ];
 
@@ -61,6 +82,7 @@ mod tests {
             let actual = CliOpt::parse_from(&input[i]);
             assert_eq!(actual.file, expect[i].file);
             assert_eq!(actual.version(), expect[i].version());
+            assert_eq!(actual.session(), expect[i].session());
         }
     }
 }
@@ -0,0 +1,120 @@
+/// Commands typed in the command popup.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Close the current view, quit when it is the last one
+    Quit,
+    /// Split the current view horizontally
+    Split,
+    /// Split the current view vertically
+    VerticalSplit,
+    /// Close the current view
+    Close,
+    /// Edit the given file in the current view
+    Edit(Option<String>),
+    /// Edit a new scratch buffer in the current view
+    EditNew,
+    /// Split the current view horizontally on a new scratch buffer
+    New,
+    /// Split the current view vertically on a new scratch buffer
+    VerticalNew,
+    /// Open a tab page, on the given file if any
+    TabNew(Option<String>),
+    /// Show the next buffer of the bufferline
+    BufferNext,
+    /// Show the previous buffer of the bufferline
+    BufferPrevious,
+    /// Go to the next tab page
+    TabNext,
+    /// Go to the previous tab page
+    TabPrevious,
+    /// Close the current tab page
+    TabClose,
+    /// List the marks
+    Marks,
+    /// List the messages shown so far
+    Messages,
+    /// Set options, buffer-local ones first
+    Set(Vec<String>),
+    /// Set buffer-local options
+    SetLocal(Vec<String>),
+    /// Switch to the given theme, show the current one if none
+    ColorScheme(Option<String>),
+    /// Change the current directory, to the home one if none
+    ChangeDirectory(Option<String>),
+    /// Save the session to the given file, to the directory auto-session if none
+    MakeSession(Option<String>),
+    /// Command not known by the editor
+    Unknown(String),
+}
+
+impl Command {
+    /// Parses the command popup input.
+    pub fn parse(input: &str) -> Command {
+        let input = input.trim();
+        let mut words = input.split_whitespace();
+        let name = words.next().unwrap_or_default();
+        let arguments: Vec<String> = words.map(str::to_string).collect();
+        let argument = arguments.first().cloned();
+        match name {
+            "q" | "quit" => Command::Quit,
+            "sp" | "split" => Command::Split,
+            "vs" | "vsplit" => Command::VerticalSplit,
+            "clo" | "close" => Command::Close,
+            "e" | "edit" => Command::Edit(argument),
+            "ene" | "enew" => Command::EditNew,
+            "new" => Command::New,
+            "vne" | "vnew" => Command::VerticalNew,
+            "tabnew" => Command::TabNew(argument),
+            "bn" | "bnext" => Command::BufferNext,
+            "bp" | "bprevious" | "bN" | "bNext" => Command::BufferPrevious,
+            "tabn" | "tabnext" => Command::TabNext,
+            "tabp" | "tabprevious" | "tabN" | "tabNext" => Command::TabPrevious,
+            "tabc" | "tabclose" => Command::TabClose,
+            "marks" => Command::Marks,
+            "mes" | "messages" => Command::Messages,
+            "se" | "set" => Command::Set(arguments),
+            "setl" | "setlocal" => Command::SetLocal(arguments),
+            "colo" | "colorscheme" => Command::ColorScheme(argument),
+            "cd" => Command::ChangeDirectory(argument),
+            "mks" | "mksession" => Command::MakeSession(argument),
+            _ => Command::Unknown(input.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn command_should_parse_when_valid_input() {
+        let input = [
+            "q",
+            " quit ",
+            "sp",
+            "vsplit",
+            "close",
+            "tabnew",
+            "tabnew src/main.rs",
+            "bn",
+            "nope",
+        ];
+
+        let expect = [
+            Command::Quit,
+            Command::Quit,
+            Command::Split,
+            Command::VerticalSplit,
+            Command::Close,
+            Command::TabNew(None),
+            Command::TabNew(Some("src/main.rs".to_string())),
+            Command::BufferNext,
+            Command::Unknown("nope".to_string()),
+        ];
+
+        assert_eq!(input.len(), expect.len());
+        for (input, expect) in input.iter().zip(expect) {
+            assert_eq!(Command::parse(input), expect);
+        }
+    }
+}
@@ -0,0 +1,102 @@
+/// What a confirm dialog asks, given back with its answer so that the asking code can go on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Question {
+    /// Save the modified buffers before quitting?
+    QuitWithChanges,
+}
+
+/// Question waiting for one of its choices, the other keys being ignored until it is answered.
+#[derive(Debug, Clone)]
+pub struct Confirm {
+    pub question: Question,
+    pub message: String,
+    /// Labels of the choices, the character following `&` being the key choosing it, as in
+    /// Vim's `confirm()`
+    pub choices: Vec<String>,
+    /// Choice made with Enter
+    pub default: usize,
+}
+
+impl Confirm {
+    pub fn new(question: Question, message: impl Into<String>, choices: &[&str]) -> Self {
+        Confirm {
+            question,
+            message: message.into(),
+            choices: choices.iter().map(|choice| choice.to_string()).collect(),
+            default: 0,
+        }
+    }
+
+    /// Index of the choice made with a key, whatever its case.
+    pub fn choice(&self, key: char) -> Option<usize> {
+        self.choices.iter().position(|choice| {
+            hotkey(choice).is_some_and(|hotkey| hotkey.eq_ignore_ascii_case(&key))
+        })
+    }
+
+    /// Choices as shown under the message: `[Y]es, (N)o, (C)ancel`, the default one in brackets.
+    pub fn choices_line(&self) -> String {
+        self.choice_labels().join(", ")
+    }
+
+    /// Labels of the choices, their key in brackets for the default one, else in parentheses.
+    pub fn choice_labels(&self) -> Vec<String> {
+        self.choices
+            .iter()
+            .enumerate()
+            .map(|(index, choice)| {
+                let (open, close) = if index == self.default {
+                    ('[', ']')
+                } else {
+                    ('(', ')')
+                };
+                match choice.split_once('&') {
+                    Some((before, after)) => {
+                        let mut after = after.chars();
+                        let key = after.next().unwrap_or_default();
+                        format!("{}{}{}{}{}", before, open, key, close, after.as_str())
+                    }
+                    None => choice.to_string(),
+                }
+            })
+            .collect()
+    }
+
+    /// Lines shown in the dialog.
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines: Vec<String> = self.message.lines().map(str::to_string).collect();
+        lines.push(String::new());
+        lines.push(self.choices_line());
+        lines
+    }
+}
+
+fn hotkey(choice: &str) -> Option<char> {
+    choice.split_once('&')?.1.chars().next()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn confirm_should_choose_with_the_marked_keys() {
+        let confirm = Confirm {
+            default: 1,
+            ..Confirm::new(
+                Question::QuitWithChanges,
+                "Save?",
+                &["&Yes", "&No", "Save &all", "Cancel"],
+            )
+        };
+        assert_eq!(confirm.choices_line(), "(Y)es, [N]o, Save (a)ll, Cancel");
+        assert_eq!(confirm.choice('y'), Some(0));
+        assert_eq!(confirm.choice('N'), Some(1));
+        assert_eq!(confirm.choice('a'), Some(2));
+        assert_eq!(confirm.choice('c'), None);
+        assert_eq!(
+            confirm.lines(),
+            ["Save?", "", "(Y)es, [N]o, Save (a)ll, Cancel"]
+        );
+    }
+}
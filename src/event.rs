@@ -4,6 +4,12 @@ use ratatui::crossterm::event::Event as CrosstermEvent;
 use std::time::Duration;
 use tokio::sync::mpsc;
 
+use crate::{
+    confirm::Question,
+    operator::{Motion, Operator},
+    view::{ScrollAnchor, Split},
+};
+
 /// The frequency at which tick events are emitted.
 const TICK_FPS: f64 = 30.0;
 
@@ -38,15 +44,48 @@ pub enum AppEvent {
     MoveUp,
     MoveRight,
     MoveDown,
+    /// Moves to the next or previous display line
+    MoveDisplayLine(bool),
+    /// Scrolls the view to put the line of the cursor at a row
+    ScrollCursorLine(ScrollAnchor),
     CreateLine,
     WriteAfterCursor(char),
     DeleteBeforeCursor,
     MoveToNextWord,
     MoveToPreviousWord,
+    OpenListingEntry,
+    OpenStartEntry(char),
+    GoToLine(Option<u16>),
+    /// Jumps to the bracket matching the first one from the cursor
+    MatchingBracket,
+    Search(String, bool),
+    NextMatch,
+    PreviousMatch,
+    JumpBack,
+    JumpForward,
+    OlderChange,
+    NewerChange,
+    AlternateBuffer,
+    Operate(Operator, Motion),
+    /// Leaves visual mode, dropping the selection
+    StopVisual,
+    Paste(bool),
+    SetMark(char),
+    JumpToMark(char, bool),
+    SplitView(Split),
+    CloseView,
+    FocusNextView,
+    FocusPreviousView,
+    NextTab,
+    PreviousTab,
     CommandPopup,
+    SearchPopup(bool),
     WriteInCommandInput(char),
     DeleteLastInCommandInput,
-    ExecuteCommand,
+    BrowseHistory(bool),
+    ExecuteCommand(String),
+    /// Choice made in a confirm dialog, none when it was cancelled
+    Answer(Question, Option<usize>),
 }
 
 /// Terminal event handler.
@@ -67,6 +106,19 @@ impl EventHandler {
         Self { sender, receiver }
     }
 
+    /// Constructs a handler only queuing the app events, without reading the terminal.
+    #[cfg(test)]
+    pub fn detached() -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        Self { sender, receiver }
+    }
+
+    /// Takes the next queued event, if any, without waiting for one.
+    #[cfg(test)]
+    pub fn try_next(&mut self) -> Option<Event> {
+        self.receiver.try_recv().ok()
+    }
+
     /// Receives an event from the sender.
     ///
     /// This function blocks until an event is received.
@@ -1,8 +1,17 @@
-use std::{collections::HashMap, fs::File, io::Read};
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{self, Read},
+    path::Path,
+};
 
-use ratatui::layout::Position;
+use serde::{Deserialize, Serialize};
 
-use crate::ui::LINE_NUMBERS_WIDTH;
+use crate::{
+    jumplist::{JumpList, Location},
+    options::BufferOptions,
+    signs::Signs,
+};
 
 pub enum Move {
     Left,
@@ -11,24 +20,95 @@ pub enum Move {
     Down,
 }
 
+/// Identifier of a buffer, given in opening order.
+pub type BufferId = usize;
+
 #[derive(Default, Debug, Clone)]
 pub struct FilesBuffers {
-    pub files: HashMap<String, FileBuffer>,
+    pub files: BTreeMap<BufferId, FileBuffer>,
+    /// Order of the buffers in the bufferline, changed by dragging them
+    pub order: Vec<BufferId>,
+    /// Identifier given to the next created buffer
+    next_id: BufferId,
+}
+
+/// What a buffer holds, deciding whether it can be modified and written to disk.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BufferKind {
+    /// Content of a file, written back to it
+    #[default]
+    File,
+    /// Throwaway text, never written
+    Scratch,
+    /// Text produced by the editor that isn't related to any file (messages, help, ...)
+    NoFile,
+    /// Content of a file that must not be written back
+    ReadOnly,
+    /// Generated listing, e.g. the entries of a directory
+    Listing,
 }
 
 #[derive(Default, Debug, Clone)]
 pub struct FileBuffer {
     pub file: Vec<Vec<char>>,
-    pub current_line: u16,
-    pub current_column: u16,
     pub lines_number: u16,
-    pub scroll_y: u16,
+    pub kind: BufferKind,
+    /// Path of the file or directory the buffer was read from
+    pub file_path: Option<String>,
+    /// Has the buffer changed since it was read or written?
+    pub modified: bool,
+    /// Positions of the last changes
+    pub changes: JumpList,
+    /// Marks local to the buffer, including the automatic ones
+    pub marks: BTreeMap<char, Location>,
+    /// Signs shown in the sign column
+    pub signs: Signs,
+    pub options: BufferOptions,
+}
+
+/// A change made to a [`FileBuffer`].
+///
+/// Edit primitives return it so that positions held outside the buffer (cursors of the views
+/// showing it, ...) can follow the text they point at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edit {
+    /// `count` chars inserted at `column` of `line`
+    InsertChars { line: u16, column: u16, count: u16 },
+    /// `count` chars removed from `column` of `line`
+    RemoveChars { line: u16, column: u16, count: u16 },
+    /// `count` lines inserted before `line`
+    InsertLines { line: u16, count: u16 },
+    /// `count` lines removed starting at `line`
+    RemoveLines { line: u16, count: u16 },
+}
+
+impl BufferKind {
+    /// Can the user edit the buffer content?
+    pub fn is_modifiable(&self) -> bool {
+        matches!(
+            self,
+            BufferKind::File | BufferKind::Scratch | BufferKind::NoFile
+        )
+    }
+
+    /// Is the buffer shown in the bufferline? Listings and text produced by the editor are not.
+    pub fn is_listed(&self) -> bool {
+        !matches!(self, BufferKind::Listing | BufferKind::NoFile)
+    }
+
+    /// Is the buffer content written to its file?
+    pub fn is_saved(&self) -> bool {
+        *self == BufferKind::File
+    }
 }
 
 impl FilesBuffers {
     pub fn new() -> Self {
         FilesBuffers {
-            files: HashMap::new(),
+            files: BTreeMap::new(),
+            order: Vec::new(),
+            next_id: 0,
         }
     }
 
@@ -36,168 +116,469 @@ impl FilesBuffers {
         self.files.is_empty()
     }
 
-    pub fn init_file_buffer(&mut self, file_path: String) {
+    /// Adds a buffer and returns its identifier.
+    pub fn create(&mut self, file_buffer: FileBuffer) -> BufferId {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.files.insert(id, file_buffer);
+        self.order.push(id);
+        id
+    }
+
+    /// Buffers shown in the bufferline, in its order.
+    pub fn listed(&self) -> Vec<BufferId> {
+        self.order
+            .iter()
+            .copied()
+            .filter(|id| {
+                self.files
+                    .get(id)
+                    .is_some_and(|file_buffer| file_buffer.kind.is_listed())
+            })
+            .collect()
+    }
+
+    /// Keeps only the buffers for which the predicate holds, dropping the others from the
+    /// bufferline too.
+    pub fn retain(&mut self, mut keep: impl FnMut(BufferId, &FileBuffer) -> bool) {
+        self.files.retain(|id, file_buffer| keep(*id, file_buffer));
+        self.order.retain(|id| self.files.contains_key(id));
+    }
+
+    /// Moves a buffer of the bufferline to the place of another one.
+    pub fn reorder(&mut self, id: BufferId, target: BufferId) {
+        let (Some(from), Some(to)) = (
+            self.order.iter().position(|other| *other == id),
+            self.order.iter().position(|other| *other == target),
+        ) else {
+            return;
+        };
+        let id = self.order.remove(from);
+        self.order.insert(to, id);
+    }
+
+    /// Opens the buffer of the given file, reading it if not already opened, and returns its id.
+    ///
+    /// Directories are opened as a listing of their entries.
+    pub fn open(&mut self, file_path: &str) -> io::Result<BufferId> {
+        let mut actual_path = file_path.to_string();
+        if let Ok(file_path) = Path::new(file_path).canonicalize()
+            && let Some(file_path) = file_path.to_str()
+        {
+            actual_path = file_path.to_string();
+        }
+        if let Some(id) = self.find(&actual_path) {
+            return Ok(id);
+        }
+        let file_buffer = if Path::new(&actual_path).is_dir() {
+            FileBuffer::from_directory(actual_path)
+        } else {
+            FileBuffer::from_file(actual_path)?
+        };
+        Ok(self.create(file_buffer))
+    }
+
+    /// Id of the buffer read from the given path.
+    pub fn find(&self, file_path: &str) -> Option<BufferId> {
+        self.files
+            .iter()
+            .find(|(_, file_buffer)| file_buffer.file_path.as_deref() == Some(file_path))
+            .map(|(id, _)| *id)
+    }
+
+    pub fn get(&self, id: BufferId) -> &FileBuffer {
+        self.files.get(&id).expect("Failed to get file buffer")
+    }
+
+    pub fn get_mut(&mut self, id: BufferId) -> &mut FileBuffer {
+        self.files.get_mut(&id).expect("Failed to get file buffer")
+    }
+}
+
+impl FileBuffer {
+    pub fn new(kind: BufferKind, file_path: Option<String>, mut file: Vec<Vec<char>>) -> Self {
+        if file.is_empty() {
+            file.push(vec![]);
+        }
+        FileBuffer {
+            lines_number: file.len() as u16,
+            file,
+            kind,
+            file_path,
+            modified: false,
+            changes: JumpList::default(),
+            marks: BTreeMap::new(),
+            signs: Signs::default(),
+            options: BufferOptions::default(),
+        }
+    }
+
+    /// Reads the given file. A file that doesn't exist yet is created on first save, a file that
+    /// can't be read or isn't valid UTF-8 is an error.
+    pub fn from_file(file_path: String) -> io::Result<Self> {
         let mut buffer = vec![];
-        let mut lines_number = 0;
-        if let Ok(mut file) = File::open(&file_path) {
-            let mut file_content = String::new();
-            file.read_to_string(&mut file_content)
-                .expect("Failed to read file buffer");
-            if file_content.lines().count() == 0 {
-                buffer.push(vec![]);
-            }
-            for line in file_content.lines() {
-                let mut buffer_line = vec![];
-                for c in line.chars() {
-                    buffer_line.push(c);
+        let mut file_content = String::new();
+        match File::open(&file_path) {
+            Ok(mut file) => {
+                file.read_to_string(&mut file_content)?;
+                for line in file_content.lines() {
+                    buffer.push(line.chars().collect());
                 }
-                buffer.push(buffer_line);
-                lines_number += 1;
             }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        let is_readonly = fs::metadata(&file_path)
+            .map(|metadata| metadata.permissions().readonly())
+            .unwrap_or(false);
+        let kind = if is_readonly {
+            BufferKind::ReadOnly
         } else {
-            buffer.push(vec![]);
+            BufferKind::File
+        };
+        let options = BufferOptions::detect(&file_path, &file_content);
+        let mut file_buffer = FileBuffer::new(kind, Some(file_path), buffer);
+        file_buffer.options = options;
+        Ok(file_buffer)
+    }
+
+    /// Lists the entries of the given directory, sub directories first.
+    pub fn from_directory(directory_path: String) -> Self {
+        let mut entries: Vec<(bool, String)> = fs::read_dir(&directory_path)
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .map(|entry| {
+                        let is_dir = entry.path().is_dir();
+                        let mut name = entry.file_name().to_string_lossy().to_string();
+                        if is_dir {
+                            name.push('/');
+                        }
+                        (!is_dir, name)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        entries.sort();
+        let mut buffer = vec!["../".chars().collect()];
+        for (_, name) in entries {
+            buffer.push(name.chars().collect());
         }
-        self.files.insert(
-            file_path,
-            FileBuffer {
-                file: buffer,
-                current_line: 0,
-                current_column: 0,
-                lines_number,
-                scroll_y: 0,
-            },
-        );
+        FileBuffer::new(BufferKind::Listing, Some(directory_path), buffer)
     }
 
-    pub fn get(&self, key: String) -> FileBuffer {
-        self.files
-            .get(&key)
-            .cloned()
-            .expect("Failed to get file buffer")
+    /// Short name of the buffer, for tabs and status.
+    pub fn name(&self) -> String {
+        let file_name = self
+            .file_path
+            .as_deref()
+            .and_then(|file_path| Path::new(file_path).file_name());
+        match (file_name, self.kind) {
+            (Some(file_name), _) => file_name.to_string_lossy().to_string(),
+            (None, BufferKind::Scratch) => "[Scratch]".to_string(),
+            (None, _) => "[No Name]".to_string(),
+        }
     }
 
-    pub fn get_mut(&mut self, key: String) -> &mut FileBuffer {
-        self.files.get_mut(&key).expect("Failed to get file buffer")
+    /// Can the content be written to the buffer file?
+    pub fn can_save(&self) -> bool {
+        self.kind.is_saved() && self.file_path.is_some()
     }
-}
 
-impl FileBuffer {
-    pub fn get_mut(&mut self) -> &mut Vec<char> {
+    pub fn line(&self, line: u16) -> &Vec<char> {
         self.file
-            .get_mut(self.current_line as usize)
+            .get(line as usize)
             .expect("Failed to get file buffer for given line")
     }
 
-    pub fn to_cursor_position(&self) -> Position {
-        Position {
-            x: self.current_column + LINE_NUMBERS_WIDTH,
-            y: self.current_line,
+    pub fn line_mut(&mut self, line: u16) -> &mut Vec<char> {
+        self.file
+            .get_mut(line as usize)
+            .expect("Failed to get file buffer for given line")
+    }
+
+    pub fn line_len(&self, line: u16) -> u16 {
+        self.line(line).len() as u16
+    }
+
+    /// Position of the next occurrence of `pattern` after the given one, wrapping around the end
+    /// of the buffer.
+    pub fn find_pattern(
+        &self,
+        pattern: &[char],
+        line: u16,
+        column: u16,
+        backward: bool,
+    ) -> Option<(u16, u16)> {
+        if pattern.is_empty() {
+            return None;
+        }
+        let lines_count = self.file.len();
+        let start = line as usize;
+        for offset in 0..=lines_count {
+            let index = if backward {
+                (start + lines_count * 2 - offset) % lines_count
+            } else {
+                (start + offset) % lines_count
+            };
+            let mut matches = self.file[index]
+                .windows(pattern.len())
+                .enumerate()
+                .filter(|(_, window)| *window == pattern)
+                .map(|(match_column, _)| match_column as u16);
+            let found = match (offset, backward) {
+                (0, false) => matches.find(|match_column| *match_column > column),
+                (0, true) => matches.rfind(|match_column| *match_column < column),
+                (_, _) if offset == lines_count && !backward => {
+                    matches.find(|match_column| *match_column <= column)
+                }
+                (_, _) if offset == lines_count => {
+                    matches.rfind(|match_column| *match_column >= column)
+                }
+                (_, false) => matches.next(),
+                (_, true) => matches.next_back(),
+            };
+            if let Some(match_column) = found {
+                return Some((index as u16, match_column));
+            }
         }
+        None
     }
 
-    pub fn create_line(&mut self) {
+    pub fn create_line(&mut self, line: u16) -> Edit {
         //TODO: Create line should create line at current position and not only after current line
-        self.file.insert(self.current_line as usize + 1, vec![]);
-        self.current_line += 1;
-        self.current_column = 0;
+        self.file.insert(line as usize + 1, vec![]);
         self.lines_number += 1;
+        Edit::InsertLines {
+            line: line + 1,
+            count: 1,
+        }
     }
 
-    pub fn delete_line(&mut self) {
-        let current_line = self.current_line as usize;
-        self.file.remove(current_line);
-        self.current_line -= 1;
-        self.current_column = self.get_mut().len() as u16;
+    pub fn delete_line(&mut self, line: u16) -> Edit {
+        self.file.remove(line as usize);
         self.lines_number -= 1;
+        Edit::RemoveLines { line, count: 1 }
     }
 
-    pub fn insert_char(&mut self, input: char) {
-        let current_column = self.current_column as usize;
-        let buffer = self.get_mut();
-        buffer.insert(current_column, input);
-        self.current_column += 1;
+    pub fn insert_char(&mut self, line: u16, column: u16, input: char) -> Edit {
+        self.line_mut(line).insert(column as usize, input);
+        Edit::InsertChars {
+            line,
+            column,
+            count: 1,
+        }
     }
 
-    pub fn delete_previous_position(&mut self) {
-        if self.current_column > 0 {
-            let current_column = self.current_column as usize - 1;
-            let buffer = self.get_mut();
-            buffer.remove(current_column);
-            self.current_column -= 1;
-        } else if self.current_line > 0 {
-            self.delete_line();
+    pub fn delete_char(&mut self, line: u16, column: u16) -> Edit {
+        self.line_mut(line).remove(column as usize);
+        Edit::RemoveChars {
+            line,
+            column,
+            count: 1,
         }
     }
 
-    pub fn move_cursor(&mut self, move_option: Move) {
-        let columns_number = self.get_mut().iter().count() as u16;
-        match move_option {
-            Move::Left => {
-                if self.current_column > 0 {
-                    self.current_column -= 1
-                }
+    pub fn insert_chars(&mut self, line: u16, column: u16, input: &[char]) -> Edit {
+        let column_index = column as usize;
+        self.line_mut(line)
+            .splice(column_index..column_index, input.iter().copied());
+        Edit::InsertChars {
+            line,
+            column,
+            count: input.len() as u16,
+        }
+    }
+
+    pub fn delete_chars(&mut self, line: u16, column: u16, count: u16) -> Edit {
+        let column_index = column as usize;
+        self.line_mut(line)
+            .drain(column_index..column_index + count as usize);
+        Edit::RemoveChars {
+            line,
+            column,
+            count,
+        }
+    }
+
+    /// Inserts lines before `line`.
+    pub fn insert_lines(&mut self, line: u16, lines: Vec<Vec<char>>) -> Edit {
+        let count = lines.len() as u16;
+        let line_index = line as usize;
+        self.file.splice(line_index..line_index, lines);
+        self.lines_number += count;
+        Edit::InsertLines { line, count }
+    }
+
+    /// Removes lines, keeping an empty line when the whole buffer is removed.
+    pub fn delete_lines(&mut self, line: u16, count: u16) -> Edit {
+        let line_index = line as usize;
+        self.file.drain(line_index..line_index + count as usize);
+        if self.file.is_empty() {
+            self.file.push(vec![]);
+        }
+        self.lines_number = self.file.len() as u16;
+        Edit::RemoveLines { line, count }
+    }
+
+    /// Text between two positions, `end` excluded.
+    pub fn text(&self, start: (u16, u16), end: (u16, u16)) -> Vec<Vec<char>> {
+        let ((start_line, start_column), (end_line, end_column)) = (start, end);
+        if start_line == end_line {
+            let line = self.line(start_line);
+            let end_column = usize::min(end_column as usize, line.len());
+            return vec![line[start_column as usize..end_column].to_vec()];
+        }
+        let mut text = vec![self.line(start_line)[start_column as usize..].to_vec()];
+        for line in start_line + 1..end_line {
+            text.push(self.line(line).clone());
+        }
+        let last_line = self.line(end_line);
+        let end_column = usize::min(end_column as usize, last_line.len());
+        text.push(last_line[..end_column].to_vec());
+        text
+    }
+}
+
+impl Edit {
+    /// Where the edit happened.
+    pub fn position(&self) -> (u16, u16) {
+        match *self {
+            Edit::InsertChars { line, column, .. } | Edit::RemoveChars { line, column, .. } => {
+                (line, column)
             }
-            Move::Up => {
-                if self.current_line > 0 {
-                    self.current_line -= 1
+            Edit::InsertLines { line, .. } | Edit::RemoveLines { line, .. } => (line, 0),
+        }
+    }
+
+    /// Last position touched by the edit.
+    pub fn last_position(&self) -> (u16, u16) {
+        match *self {
+            Edit::InsertChars {
+                line,
+                column,
+                count,
+            } => (line, column + count.saturating_sub(1)),
+            Edit::InsertLines { line, count } => (line + count.saturating_sub(1), 0),
+            Edit::RemoveChars { .. } | Edit::RemoveLines { .. } => self.position(),
+        }
+    }
+
+    /// Moves the given position so that it keeps pointing at the same text after the edit.
+    ///
+    /// Returns `false` when the text the position pointed at has been removed, in which case the
+    /// position is moved to the start of the removed range.
+    pub fn shift(&self, line: &mut u16, column: &mut u16) -> bool {
+        match *self {
+            Edit::InsertChars {
+                line: edit_line,
+                column: edit_column,
+                count,
+            } => {
+                if *line == edit_line && *column >= edit_column {
+                    *column += count;
                 }
             }
-            Move::Right => {
-                if self.current_column < columns_number {
-                    self.current_column += 1
+            Edit::RemoveChars {
+                line: edit_line,
+                column: edit_column,
+                count,
+            } => {
+                if *line == edit_line && *column >= edit_column {
+                    if *column < edit_column + count {
+                        *column = edit_column;
+                        return false;
+                    }
+                    *column -= count;
                 }
             }
-            Move::Down => {
-                if self.current_line + 1 < self.lines_number {
-                    self.current_line += 1
+            Edit::InsertLines {
+                line: edit_line,
+                count,
+            } => {
+                if *line >= edit_line {
+                    *line += count;
                 }
             }
-        };
-    }
-
-    pub fn move_to_next_word(&mut self) {
-        let columns_number = self.get_mut().iter().count() as u16;
-        let mut index = self.current_column;
-        {
-            let line = self.get_mut();
-            let mut is_parsing_word = false;
-            while index < columns_number {
-                let Some(c) = line.get(index as usize) else {
-                    return;
-                };
-                if is_parsing_word && *c == ' ' {
-                    break;
+            Edit::RemoveLines {
+                line: edit_line,
+                count,
+            } => {
+                if *line >= edit_line + count {
+                    *line -= count;
+                } else if *line >= edit_line {
+                    *line = edit_line;
+                    *column = 0;
+                    return false;
                 }
-                if *c != ' ' {
-                    is_parsing_word = true;
-                }
-                index += 1;
             }
         }
-        self.current_column = index;
+        true
     }
+}
 
-    pub fn move_to_previous_word(&mut self) {
-        if self.current_column < 1 {
-            return;
-        };
-        let mut index = self.current_column - 1;
-        {
-            let line = self.get_mut();
-            let mut is_parsing_word = false;
-            while index > 0 {
-                let Some(c) = line.get(index as usize) else {
-                    return;
-                };
-                if is_parsing_word && *c == ' ' {
-                    break;
-                }
-                if *c != ' ' {
-                    is_parsing_word = true;
-                }
-                index -= 1;
-            }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn edit_shift_should_follow_text() {
+        let edits = [
+            Edit::InsertChars {
+                line: 1,
+                column: 2,
+                count: 3,
+            },
+            Edit::RemoveChars {
+                line: 1,
+                column: 2,
+                count: 2,
+            },
+            Edit::InsertLines { line: 1, count: 2 },
+            Edit::RemoveLines { line: 0, count: 1 },
+            Edit::RemoveLines { line: 1, count: 1 },
+        ];
+
+        let expect = [
+            (1, 7, true),
+            (1, 2, true),
+            (3, 4, true),
+            (0, 4, true),
+            (1, 0, false),
+        ];
+
+        assert_eq!(edits.len(), expect.len());
+        for (edit, expect) in edits.iter().zip(expect) {
+            let (mut line, mut column) = (1, 4);
+            let kept = edit.shift(&mut line, &mut column);
+            assert_eq!((line, column, kept), expect, "{:?}", edit);
         }
-        self.current_column = index;
+    }
+
+    #[test]
+    fn open_should_fail_on_files_that_cant_be_read_as_text() {
+        let directory = env::temp_dir().join(format!("oxide-open-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let path = |name: &str| directory.join(name).to_string_lossy().to_string();
+        fs::write(path("binary"), [0xff, 0xfe, 0x00]).unwrap();
+        fs::write(path("text"), "text\n").unwrap();
+
+        let mut buffers = FilesBuffers::new();
+        let binary = buffers.open(&path("binary"));
+        let text = buffers.open(&path("text"));
+        let new = buffers.open(&path("new"));
+        fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(
+            binary.map_err(|e| e.kind()),
+            Err(io::ErrorKind::InvalidData)
+        );
+        assert_eq!(
+            buffers.get(text.unwrap()).file,
+            vec!["text".chars().collect::<Vec<_>>()]
+        );
+        assert_eq!(buffers.get(new.unwrap()).file, vec![vec![]]);
+        assert_eq!(buffers.files.len(), 2);
     }
 }
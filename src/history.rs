@@ -0,0 +1,62 @@
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+
+/// Maximum number of remembered entries.
+const MAX_LENGTH: usize = 100;
+
+/// Line typed in the command popup, or path of an opened file.
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub text: String,
+    /// Unix timestamp of the last time the line was typed
+    pub time: i64,
+}
+
+/// Lines typed in the command popup or paths of opened files, oldest first.
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct History {
+    pub entries: Vec<HistoryEntry>,
+}
+
+impl History {
+    /// Remembers a line, moving it last if already known.
+    pub fn push(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        self.insert(HistoryEntry {
+            text: text.to_string(),
+            time: Local::now().timestamp(),
+        });
+    }
+
+    /// Entry `index` steps back from the most recent one.
+    pub fn get(&self, index: usize) -> Option<&str> {
+        let position = self.entries.len().checked_sub(index + 1)?;
+        self.entries.get(position).map(|entry| entry.text.as_str())
+    }
+
+    /// Adds the entries of another history, keeping the most recent time of each line.
+    pub fn merge(&mut self, other: History) {
+        for entry in other.entries {
+            self.insert(entry);
+        }
+    }
+
+    fn insert(&mut self, entry: HistoryEntry) {
+        if let Some(known) = self.entries.iter().find(|known| known.text == entry.text)
+            && known.time > entry.time
+        {
+            return;
+        }
+        self.entries.retain(|known| known.text != entry.text);
+        let position = self
+            .entries
+            .partition_point(|known| known.time <= entry.time);
+        self.entries.insert(position, entry);
+        if self.entries.len() > MAX_LENGTH {
+            self.entries.remove(0);
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use crate::filesbuffers::{BufferId, Edit};
+
+/// Maximum number of remembered positions.
+const MAX_LENGTH: usize = 100;
+
+/// Position in a buffer.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location {
+    pub buffer_id: BufferId,
+    pub line: u16,
+    pub column: u16,
+}
+
+/// Positions walked back and forth, used for the jump list and the change list.
+///
+/// The positions follow the edits of their buffer.
+#[derive(Default, Debug, Clone)]
+pub struct JumpList {
+    pub entries: Vec<Location>,
+    /// Position in the entries, `entries.len()` when not walking the list
+    pub index: usize,
+}
+
+impl Location {
+    /// Keeps the location pointing at the same text after an edit of its buffer.
+    ///
+    /// Returns `false` when the text it pointed at has been removed.
+    pub fn apply_edit(&mut self, buffer_id: BufferId, edit: Edit) -> bool {
+        self.buffer_id != buffer_id || edit.shift(&mut self.line, &mut self.column)
+    }
+}
+
+impl JumpList {
+    /// Remembers a position, forgetting older ones on the same line, and stops walking the list.
+    pub fn push(&mut self, location: Location) {
+        self.entries
+            .retain(|entry| entry.buffer_id != location.buffer_id || entry.line != location.line);
+        self.entries.push(location);
+        if self.entries.len() > MAX_LENGTH {
+            self.entries.remove(0);
+        }
+        self.index = self.entries.len();
+    }
+
+    /// Remembers a change position. Consecutive changes on a line only keep the last one.
+    pub fn push_change(&mut self, location: Location) {
+        if let Some(last) = self.entries.last_mut()
+            && last.line == location.line
+        {
+            *last = location;
+            self.index = self.entries.len();
+            return;
+        }
+        self.push(location);
+    }
+
+    /// Walks back to the previous position.
+    ///
+    /// When starting to walk, `current` is remembered so that [`JumpList::forward`] can come back
+    /// to it.
+    pub fn back(&mut self, current: Option<Location>) -> Option<Location> {
+        if self.index == self.entries.len()
+            && let Some(current) = current
+        {
+            self.push(current);
+            self.index -= 1;
+        }
+        if self.index == 0 {
+            return None;
+        }
+        self.index -= 1;
+        self.entries.get(self.index).copied()
+    }
+
+    /// Walks forward to the next position.
+    pub fn forward(&mut self) -> Option<Location> {
+        if self.index + 1 >= self.entries.len() {
+            return None;
+        }
+        self.index += 1;
+        self.entries.get(self.index).copied()
+    }
+
+    /// Keeps the positions pointing at the same text after an edit of their buffer.
+    pub fn apply_edit(&mut self, buffer_id: BufferId, edit: Edit) {
+        for entry in self.entries.iter_mut() {
+            entry.apply_edit(buffer_id, edit);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn location(line: u16) -> Location {
+        Location {
+            buffer_id: 0,
+            line,
+            column: 0,
+        }
+    }
+
+    #[test]
+    fn jump_list_should_walk_back_and_forth() {
+        let mut jumps = JumpList::default();
+        jumps.push(location(1));
+        jumps.push(location(5));
+
+        assert_eq!(jumps.back(Some(location(9))), Some(location(5)));
+        assert_eq!(jumps.back(Some(location(5))), Some(location(1)));
+        assert_eq!(jumps.back(Some(location(1))), None);
+        assert_eq!(jumps.forward(), Some(location(5)));
+        assert_eq!(jumps.forward(), Some(location(9)));
+        assert_eq!(jumps.forward(), None);
+    }
+
+    #[test]
+    fn location_should_follow_the_edits_of_its_buffer() {
+        let edits = [
+            (0, Edit::InsertLines { line: 0, count: 2 }),
+            (1, Edit::InsertLines { line: 0, count: 2 }),
+            (0, Edit::RemoveLines { line: 0, count: 1 }),
+            (0, Edit::RemoveLines { line: 3, count: 1 }),
+        ];
+        let expect = [(5, true), (3, true), (2, true), (3, false)];
+        for ((buffer_id, edit), expect) in edits.into_iter().zip(expect) {
+            let mut location = location(3);
+            let kept = location.apply_edit(buffer_id, edit);
+            assert_eq!((location.line, kept), expect, "{} {:?}", buffer_id, edit);
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use std::time::Duration;
+
+use ratatui::{
+    style::Style,
+    text::{Line, Span},
+};
+use unicode_width::UnicodeWidthStr;
+
+use crate::operator::Operator;
+
+/// Pause after a prefix key before its continuations get shown.
+pub const KEY_HINTS_DELAY: Duration = Duration::from_millis(500);
+
+const WINDOW_KEYS: &[(&str, &str)] = &[
+    ("w", "focus the next view"),
+    ("W", "focus the previous view"),
+    ("s", "split horizontally"),
+    ("v", "split vertically"),
+    ("c", "close the view"),
+    ("q", "close the view"),
+];
+
+const GO_KEYS: &[(&str, &str)] = &[
+    ("g", "first line, or line {count}"),
+    ("j", "display line below"),
+    ("k", "display line above"),
+    ("t", "next tab page"),
+    ("T", "previous tab page"),
+    (";", "older change"),
+    (",", "newer change"),
+];
+
+const SCROLL_KEYS: &[(&str, &str)] = &[
+    ("t", "cursor line at the top"),
+    ("z", "cursor line at the center"),
+    ("b", "cursor line at the bottom"),
+];
+
+const MOTION_KEYS: &[(&str, &str)] = &[
+    ("h", "character left"),
+    ("j", "line below"),
+    ("k", "line above"),
+    ("l", "character right"),
+    ("w", "next word"),
+    ("b", "previous word"),
+    ("G", "last line, or line {count}"),
+    ("gg", "first line, or line {count}"),
+    ("%", "matching bracket"),
+    ("'", "line of a mark"),
+    ("`", "position of a mark"),
+];
+
+/// Keys that can follow the pending ones, with what they do, or nothing when no sequence is
+/// pending.
+pub fn key_hints(pending_keys: &str) -> Vec<(String, &'static str)> {
+    let hints = |keys: &[(&str, &'static str)]| {
+        keys.iter()
+            .map(|(key, description)| (key.to_string(), *description))
+            .collect()
+    };
+    match pending_keys {
+        "<C-w>" => hints(WINDOW_KEYS),
+        "g" => hints(GO_KEYS),
+        "z" => hints(SCROLL_KEYS),
+        "m" => hints(&[("a-z", "set a mark"), ("A-Z", "set a file mark")]),
+        "'" => hints(&[
+            ("a-z A-Z", "line of the mark"),
+            ("'", "line before the jump"),
+        ]),
+        "`" => hints(&[
+            ("a-z A-Z", "position of the mark"),
+            ("`", "position before the jump"),
+        ]),
+        "\"" => hints(&[("a-z", "named register"), ("\"", "unnamed register")]),
+        _ => {
+            let mut keys = pending_keys.chars();
+            let Some(operator) = keys.next().and_then(Operator::from_key) else {
+                return Vec::new();
+            };
+            match keys.as_str() {
+                "" => {
+                    let mut hints: Vec<(String, &str)> = hints(MOTION_KEYS);
+                    hints.push((operator.key().to_string(), "whole line"));
+                    hints
+                }
+                "g" => hints(&[("g", "up to the first line")]),
+                "'" => hints(&[("a-z A-Z", "up to the line of the mark")]),
+                "`" => hints(&[("a-z A-Z", "up to the mark")]),
+                _ => Vec::new(),
+            }
+        }
+    }
+}
+
+/// Lines laying out the hints in as many columns as fit in the width, filled from top to bottom.
+pub fn key_hints_lines(
+    hints: &[(String, &'static str)],
+    width: u16,
+    key_style: Style,
+) -> Vec<Line<'static>> {
+    let key_width = hints.iter().map(|(key, _)| key.width()).max().unwrap_or(0);
+    let description_width = hints
+        .iter()
+        .map(|(_, description)| description.width())
+        .max()
+        .unwrap_or(0);
+    let entry_width = key_width + description_width + 5;
+    let columns = usize::max(width as usize / entry_width, 1);
+    let rows = hints.len().div_ceil(columns);
+    (0..rows)
+        .map(|row| {
+            let spans = hints
+                .iter()
+                .skip(row)
+                .step_by(rows)
+                .flat_map(|(key, description)| {
+                    [
+                        Span::styled(format!("{:>width$}", key, width = key_width), key_style),
+                        Span::raw(format!(
+                            " → {:width$}  ",
+                            description,
+                            width = description_width
+                        )),
+                    ]
+                });
+            Line::from(spans.collect::<Vec<Span>>())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_hints_should_list_the_continuations_of_a_prefix() {
+        let keys = |pending_keys| -> Vec<String> {
+            key_hints(pending_keys)
+                .into_iter()
+                .map(|(key, _)| key)
+                .collect()
+        };
+        assert_eq!(keys("z"), ["t", "z", "b"]);
+        assert_eq!(keys("<C-w>").len(), 6);
+        assert_eq!(keys("m"), ["a-z", "A-Z"]);
+        assert_eq!(keys("d").last().map(String::as_str), Some("d"));
+        assert_eq!(keys("yg"), ["g"]);
+        assert!(keys("").is_empty());
+        assert!(keys("x").is_empty());
+    }
+}
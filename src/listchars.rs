@@ -0,0 +1,87 @@
+/// Glyphs shown in `list` mode in place of the invisible characters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListChars {
+    /// First cell of a tab, the cells filling the rest of it, and its last cell if set
+    pub tab: Option<(char, char, Option<char>)>,
+    /// Spaces at the end of a line
+    pub trail: Option<char>,
+    /// Non-breaking spaces
+    pub nbsp: Option<char>,
+    /// Shown after the last character of a line
+    pub eol: Option<char>,
+}
+
+impl Default for ListChars {
+    fn default() -> Self {
+        ListChars {
+            tab: Some(('>', ' ', None)),
+            trail: Some('-'),
+            nbsp: Some('+'),
+            eol: None,
+        }
+    }
+}
+
+impl ListChars {
+    /// Parses the `listchars` option: `name:glyphs` settings separated by commas, such as
+    /// `tab:»·,trail:·,eol:$`. The characters not named are shown as is.
+    pub fn parse(value: &str) -> Result<ListChars, String> {
+        let mut list_chars = ListChars {
+            tab: None,
+            trail: None,
+            nbsp: None,
+            eol: None,
+        };
+        for setting in value.split(',').filter(|setting| !setting.is_empty()) {
+            let invalid = || format!("Invalid listchars: {}", setting);
+            let (name, glyphs) = setting.split_once(':').ok_or_else(invalid)?;
+            let glyphs: Vec<char> = glyphs.chars().collect();
+            match (name, glyphs.as_slice()) {
+                ("tab", [first, fill]) => list_chars.tab = Some((*first, *fill, None)),
+                ("tab", [first, fill, last]) => list_chars.tab = Some((*first, *fill, Some(*last))),
+                ("trail", [glyph]) => list_chars.trail = Some(*glyph),
+                ("nbsp", [glyph]) => list_chars.nbsp = Some(*glyph),
+                ("eol", [glyph]) => list_chars.eol = Some(*glyph),
+                _ => return Err(invalid()),
+            }
+        }
+        Ok(list_chars)
+    }
+
+    /// Glyphs of a tab spreading over the given number of cells.
+    pub fn tab_glyphs(&self, width: usize) -> Option<String> {
+        let (first, fill, last) = self.tab?;
+        Some(match (width, last) {
+            (1, Some(last)) => last.to_string(),
+            (_, Some(last)) => {
+                let fill: String = std::iter::repeat_n(fill, width.saturating_sub(2)).collect();
+                format!("{}{}{}", first, fill, last)
+            }
+            _ => std::iter::once(first)
+                .chain(std::iter::repeat_n(fill, width.saturating_sub(1)))
+                .collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn listchars_should_parse_settings() {
+        let list_chars = ListChars::parse("tab:<->,trail:·,eol:$").unwrap();
+        assert_eq!(list_chars.tab_glyphs(4), Some("<-->".to_string()));
+        assert_eq!(list_chars.tab_glyphs(1), Some(">".to_string()));
+        assert_eq!(list_chars.trail, Some('·'));
+        assert_eq!(list_chars.nbsp, None);
+        assert_eq!(list_chars.eol, Some('$'));
+
+        assert_eq!(
+            ListChars::parse("tab:»·").unwrap().tab_glyphs(3),
+            Some("»··".to_string())
+        );
+        assert!(ListChars::parse("tab:>").is_err());
+        assert!(ListChars::parse("space:.").is_err());
+    }
+}
@@ -1,14 +1,41 @@
 use app::APP_NAME;
 use clap::Parser;
 use cli::CliOpt;
+use ratatui::crossterm::{event::DisableMouseCapture, execute};
+use std::{io::stdout, panic};
 
 use crate::app::Editor;
 
 pub mod app;
+pub mod brackets;
+pub mod bufferline;
 pub mod cli;
+pub mod command;
+pub mod confirm;
 pub mod event;
 pub mod filesbuffers;
+pub mod history;
+pub mod jumplist;
+pub mod keyhints;
+pub mod listchars;
+pub mod messages;
+pub mod minimap;
+pub mod operator;
+pub mod options;
+pub mod paths;
+pub mod popup;
+pub mod registers;
+pub mod session;
+pub mod signs;
+pub mod startscreen;
+pub mod state;
+pub mod statusline;
+pub mod tabpage;
+pub mod text;
+pub mod theme;
 pub mod ui;
+pub mod view;
+pub mod wrap;
 
 #[tokio::main]
 async fn main() -> color_eyre::Result<()> {
@@ -21,7 +48,14 @@ async fn main() -> color_eyre::Result<()> {
     }
     color_eyre::install()?;
     let terminal = ratatui::init();
+    // Gives the mouse back to the terminal however the editor stops
+    let restore_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let _ = execute!(stdout(), DisableMouseCapture);
+        restore_hook(info);
+    }));
     let result = Editor::new(cli_opt).run(terminal).await;
+    let _ = execute!(stdout(), DisableMouseCapture);
     ratatui::restore();
     result
 }
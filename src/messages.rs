@@ -0,0 +1,212 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Local};
+
+use crate::paths::state_dir;
+
+/// Number of messages kept in the history.
+const MAX_MESSAGES: usize = 200;
+/// Time a message stays on the message line.
+const MESSAGE_DURATION: Duration = Duration::from_secs(4);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Level {
+    fn name(self) -> &'static str {
+        match self {
+            Level::Info => "info",
+            Level::Warning => "warning",
+            Level::Error => "error",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Message {
+    pub level: Level,
+    /// May span several lines
+    pub text: String,
+    pub time: DateTime<Local>,
+}
+
+/// Messages shown to the user, and their history.
+#[derive(Debug, Default)]
+pub struct Messages {
+    history: Vec<Message>,
+    /// Number of the latest messages shown since the last key press
+    shown: usize,
+    /// When the last message was shown, none once it is cleared
+    shown_at: Option<Instant>,
+    /// Whether the next message replaces the shown ones
+    new_group: bool,
+    /// Whether the shown messages wait for Enter to be pressed, as they don't fit on the line
+    pub prompt: bool,
+    /// File where the warnings and errors are logged, none to keep them in memory only
+    log_path: Option<PathBuf>,
+}
+
+impl Messages {
+    /// Messages logging their warnings and errors to the given file.
+    pub fn new(log_path: Option<PathBuf>) -> Self {
+        Messages {
+            log_path,
+            ..Messages::default()
+        }
+    }
+
+    pub fn info(&mut self, text: impl Into<String>) {
+        self.push(Level::Info, text.into());
+    }
+
+    pub fn warning(&mut self, text: impl Into<String>) {
+        self.push(Level::Warning, text.into());
+    }
+
+    pub fn error(&mut self, text: impl Into<String>) {
+        self.push(Level::Error, text.into());
+    }
+
+    /// Shows a message, warnings and errors being also written to the log file.
+    pub fn push(&mut self, level: Level, text: String) {
+        let message = Message {
+            level,
+            text,
+            time: Local::now(),
+        };
+        if level != Level::Info
+            && let Some(log_path) = &self.log_path
+        {
+            log(log_path, &message);
+        }
+        if self.history.len() == MAX_MESSAGES {
+            self.history.remove(0);
+        }
+        self.history.push(message);
+        if self.new_group {
+            self.shown = 0;
+            self.new_group = false;
+        }
+        self.shown = usize::min(self.shown + 1, self.history.len());
+        self.shown_at = Some(Instant::now());
+        self.prompt = self.shown > 1 || self.history.last().is_some_and(|m| m.text.contains('\n'));
+    }
+
+    /// Messages on screen, the oldest first.
+    pub fn shown(&self) -> &[Message] {
+        if self.shown_at.is_none() {
+            return &[];
+        }
+        &self.history[self.history.len() - self.shown..]
+    }
+
+    /// Starts a new group of messages, a key having been pressed. A message on the line stays
+    /// until it expires or gets replaced, the ones waiting for Enter are cleared.
+    pub fn acknowledge(&mut self) {
+        if self.prompt {
+            self.prompt = false;
+            self.shown_at = None;
+        }
+        self.new_group = true;
+    }
+
+    /// Clears the message line once the message has been shown long enough. Returns whether it
+    /// was cleared.
+    pub fn expire(&mut self) -> bool {
+        let expired = !self.prompt
+            && self
+                .shown_at
+                .is_some_and(|shown_at| shown_at.elapsed() >= MESSAGE_DURATION);
+        if expired {
+            self.shown_at = None;
+            self.shown = 0;
+        }
+        expired
+    }
+
+    /// Lines of the `:messages` listing.
+    pub fn listing(&self) -> Vec<Vec<char>> {
+        self.history
+            .iter()
+            .flat_map(|message| {
+                let time = message.time.format("%H:%M:%S");
+                let level = message.level.name();
+                message
+                    .text
+                    .lines()
+                    .map(move |line| format!("{} {:<7} {}", time, level, line).chars().collect())
+            })
+            .collect()
+    }
+}
+
+/// File where the warnings and errors are logged.
+pub fn log_path() -> Option<PathBuf> {
+    Some(state_dir()?.join("oxide.log"))
+}
+
+/// Appends a message to the log file, silently giving up when it can't be written.
+fn log(path: &Path, message: &Message) {
+    if let Some(directory) = path.parent() {
+        let _ = fs::create_dir_all(directory);
+    }
+    if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(path) {
+        let _ = writeln!(
+            file,
+            "{} {} {}",
+            message.time.to_rfc3339(),
+            message.level.name(),
+            message.text
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages_should_prompt_when_they_dont_fit() {
+        let mut messages = Messages::default();
+        messages.push(Level::Info, "written".to_string());
+        assert!(!messages.prompt);
+        messages.acknowledge();
+        assert_eq!(messages.shown()[0].text, "written");
+
+        messages.acknowledge();
+        messages.push(Level::Info, "first".to_string());
+        messages.push(Level::Info, "second".to_string());
+        assert!(messages.prompt);
+        assert_eq!(messages.shown().len(), 2);
+
+        messages.acknowledge();
+        assert!(messages.shown().is_empty());
+        messages.push(Level::Info, "one\ntwo".to_string());
+        assert!(messages.prompt);
+    }
+
+    #[test]
+    fn messages_should_log_warnings_and_errors_to_their_file() {
+        let path = std::env::temp_dir().join(format!("oxide-log-{}/oxide.log", std::process::id()));
+        let mut messages = Messages::new(Some(path.clone()));
+        messages.info("written");
+        messages.warning("careful");
+        messages.error("failed");
+        let mut unlogged = Messages::default();
+        unlogged.error("lost");
+
+        let log = fs::read_to_string(&path).unwrap();
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+        let lines: Vec<&str> = log.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with(" warning careful"));
+        assert!(lines[1].ends_with(" error failed"));
+    }
+}
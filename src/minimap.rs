@@ -0,0 +1,69 @@
+use crate::text::expand_tabs;
+
+/// Number of buffer lines drawn in a row of the minimap, one per braille dot.
+pub const LINES_PER_ROW: usize = 4;
+/// Number of display columns of the text covered by a braille dot.
+const COLUMNS_PER_DOT: usize = 2;
+/// Bits of the braille dots, by row then by column.
+const DOTS: [[u32; 2]; LINES_PER_ROW] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+/// First line shown by a minimap of the given height, so that it scrolls along with the view:
+/// the top of the buffer when the view is at its top, the bottom when the view is at its bottom.
+pub fn first_line(
+    lines_number: usize,
+    scroll_y: usize,
+    view_height: usize,
+    height: usize,
+) -> usize {
+    let capacity = height * LINES_PER_ROW;
+    if lines_number <= capacity {
+        return 0;
+    }
+    let scrollable = lines_number.saturating_sub(view_height).max(1);
+    let first = (lines_number - capacity) * usize::min(scroll_y, scrollable) / scrollable;
+    // Keeps the rows aligned on whole groups of lines, so that they don't flicker when scrolling
+    first - first % LINES_PER_ROW
+}
+
+/// Braille characters drawing the text of up to [`LINES_PER_ROW`] lines, a dot being raised
+/// when its columns hold anything but blanks.
+pub fn braille_row(lines: &[Vec<char>], width: usize, tabstop: u16) -> String {
+    let mut cells = vec![0; width];
+    for (line_index, line) in lines.iter().take(LINES_PER_ROW).enumerate() {
+        let text: Vec<char> = expand_tabs(line, tabstop).chars().collect();
+        for (dot, columns) in text.chunks(COLUMNS_PER_DOT).enumerate() {
+            if dot / 2 >= width {
+                break;
+            }
+            if columns.iter().any(|c| !c.is_whitespace()) {
+                cells[dot / 2] |= DOTS[line_index][dot % 2];
+            }
+        }
+    }
+    cells
+        .into_iter()
+        .map(|bits| match bits {
+            0 => ' ',
+            _ => char::from_u32(0x2800 + bits).unwrap_or(' '),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn minimap_should_draw_and_scroll_with_the_view() {
+        let lines: Vec<Vec<char>> = ["abcd", "  ef", "", "g"]
+            .iter()
+            .map(|line| line.chars().collect())
+            .collect();
+        assert_eq!(braille_row(&lines, 2, 4), "\u{2859} ");
+
+        assert_eq!(first_line(30, 0, 10, 10), 0);
+        assert_eq!(first_line(200, 0, 20, 10), 0);
+        assert_eq!(first_line(200, 180, 20, 10), 160);
+        assert_eq!(first_line(200, 90, 20, 10), 80);
+    }
+}
@@ -0,0 +1,104 @@
+/// Operators applied to the text covered by a motion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Delete,
+    Yank,
+    /// Delete then write in insert mode
+    Change,
+}
+
+/// Motions giving the text an operator applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Motion {
+    Left,
+    Up,
+    Right,
+    Down,
+    NextWord,
+    PreviousWord,
+    /// Current line, when the operator key is repeated
+    Line,
+    /// Given line, the last one if none
+    GoToLine(Option<u16>),
+    /// Position of a mark when exact, else its whole line
+    Mark(char, bool),
+    /// Bracket matching the first one from the cursor, included
+    MatchingBracket,
+}
+
+/// Text between two positions of a buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Range {
+    pub start: (u16, u16),
+    /// End of the text, excluded unless the range is linewise
+    pub end: (u16, u16),
+    /// Does the range cover whole lines?
+    pub linewise: bool,
+}
+
+impl Operator {
+    pub fn from_key(key: char) -> Option<Operator> {
+        match key {
+            'd' => Some(Operator::Delete),
+            'y' => Some(Operator::Yank),
+            'c' => Some(Operator::Change),
+            _ => None,
+        }
+    }
+
+    pub fn key(&self) -> char {
+        match self {
+            Operator::Delete => 'd',
+            Operator::Yank => 'y',
+            Operator::Change => 'c',
+        }
+    }
+}
+
+impl Range {
+    /// Range between two positions, whatever their order.
+    pub fn new(from: (u16, u16), to: (u16, u16), linewise: bool) -> Self {
+        let (start, end) = if to < from { (to, from) } else { (from, to) };
+        if linewise {
+            Range {
+                start: (start.0, 0),
+                end: (end.0, 0),
+                linewise,
+            }
+        } else {
+            Range {
+                start,
+                end,
+                linewise,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn range_should_order_its_positions() {
+        let inputs = [
+            ((1, 2), (3, 4), false),
+            ((3, 4), (1, 2), false),
+            ((1, 6), (1, 2), false),
+            ((3, 4), (1, 2), true),
+            ((2, 5), (2, 1), true),
+        ];
+        let expect = [
+            ((1, 2), (3, 4)),
+            ((1, 2), (3, 4)),
+            ((1, 2), (1, 6)),
+            ((1, 0), (3, 0)),
+            ((2, 0), (2, 0)),
+        ];
+        for ((from, to, linewise), expect) in inputs.into_iter().zip(expect) {
+            let range = Range::new(from, to, linewise);
+            assert_eq!((range.start, range.end), expect, "{:?} {:?}", from, to);
+            assert_eq!(range.linewise, linewise);
+        }
+    }
+}
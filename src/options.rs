@@ -0,0 +1,347 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::listchars::ListChars;
+use crate::statusline::StatusLine;
+use unicode_width::UnicodeWidthStr;
+
+/// Options shared by all the buffers, changed with `:set`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+    /// Show the line numbers in the gutter
+    pub number: bool,
+    /// Show the distance to the cursor line in the gutter, with its number as well if `number`
+    /// is set
+    pub relativenumber: bool,
+    /// When to show the sign column: "auto" when the buffer has signs, "yes" or "no"
+    pub signcolumn: String,
+    /// Wrap long lines instead of scrolling horizontally
+    pub wrap: bool,
+    /// Text shown at the start of the display lines continuing a wrapped line
+    pub showbreak: String,
+    /// Minimal number of columns kept on each side of the cursor when scrolling horizontally
+    pub sidescrolloff: u16,
+    /// Segments shown in the statusline
+    pub statusline: StatusLine,
+    /// Highlight the line of the cursor
+    pub cursorline: bool,
+    /// Display columns highlighted as rulers, starting at 1
+    pub colorcolumn: Vec<u16>,
+    /// Show the invisible characters with the `listchars` glyphs
+    pub list: bool,
+    pub listchars: ListChars,
+    /// Draw a guide at each level of indentation
+    pub indentguides: bool,
+    /// Show an overview of the buffer on the right of the views
+    pub minimap: bool,
+    /// Number of cells of the minimap
+    pub minimapwidth: u16,
+    /// Capture the mouse, to click, select, scroll and resize the views. Off by default, leaving
+    /// the selection and copy to the terminal
+    pub mouse: bool,
+    /// Show the open buffers in a line at the top
+    pub bufferline: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            number: true,
+            relativenumber: false,
+            signcolumn: "auto".to_string(),
+            wrap: false,
+            showbreak: String::new(),
+            sidescrolloff: 0,
+            statusline: StatusLine::default(),
+            cursorline: false,
+            colorcolumn: Vec::new(),
+            list: false,
+            listchars: ListChars::default(),
+            indentguides: false,
+            minimap: false,
+            minimapwidth: 10,
+            mouse: false,
+            bufferline: false,
+        }
+    }
+}
+
+impl Options {
+    /// Does the `:set` argument name a global option?
+    pub fn is_global(argument: &str) -> bool {
+        let name = argument.split(['=', '!']).next().unwrap_or_default();
+        matches!(
+            name,
+            "number"
+                | "nu"
+                | "nonumber"
+                | "nonu"
+                | "invnumber"
+                | "invnu"
+                | "relativenumber"
+                | "rnu"
+                | "norelativenumber"
+                | "nornu"
+                | "invrelativenumber"
+                | "invrnu"
+                | "signcolumn"
+                | "scl"
+                | "wrap"
+                | "nowrap"
+                | "invwrap"
+                | "showbreak"
+                | "sbr"
+                | "sidescrolloff"
+                | "siso"
+                | "statusline"
+                | "stl"
+                | "cursorline"
+                | "cul"
+                | "nocursorline"
+                | "nocul"
+                | "invcursorline"
+                | "invcul"
+                | "colorcolumn"
+                | "cc"
+                | "list"
+                | "nolist"
+                | "invlist"
+                | "listchars"
+                | "lcs"
+                | "indentguides"
+                | "noindentguides"
+                | "invindentguides"
+                | "minimap"
+                | "nominimap"
+                | "invminimap"
+                | "minimapwidth"
+                | "mouse"
+                | "nomouse"
+                | "invmouse"
+                | "bufferline"
+                | "nobufferline"
+                | "invbufferline"
+        )
+    }
+
+    /// Number of cells of the `showbreak` marker.
+    pub fn showbreak_width(&self) -> u16 {
+        self.showbreak.width() as u16
+    }
+
+    /// Applies a `:set` argument: `name`, `noname`, `name!` or `name=value`.
+    pub fn set(&mut self, argument: &str) -> Result<(), String> {
+        let (name, value) = match argument.split_once('=') {
+            Some((name, value)) => (name, Some(value)),
+            None => (argument, None),
+        };
+        match (name, value) {
+            ("number" | "nu", None) => self.number = true,
+            ("nonumber" | "nonu", None) => self.number = false,
+            ("number!" | "nu!" | "invnumber" | "invnu", None) => self.number = !self.number,
+            ("relativenumber" | "rnu", None) => self.relativenumber = true,
+            ("norelativenumber" | "nornu", None) => self.relativenumber = false,
+            ("relativenumber!" | "rnu!" | "invrelativenumber" | "invrnu", None) => {
+                self.relativenumber = !self.relativenumber
+            }
+            ("signcolumn" | "scl", Some(value @ ("auto" | "yes" | "no"))) => {
+                self.signcolumn = value.to_string()
+            }
+            ("wrap", None) => self.wrap = true,
+            ("nowrap", None) => self.wrap = false,
+            ("wrap!" | "invwrap", None) => self.wrap = !self.wrap,
+            ("showbreak" | "sbr", Some(value)) => self.showbreak = value.to_string(),
+            ("sidescrolloff" | "siso", Some(value)) => {
+                self.sidescrolloff = value
+                    .parse()
+                    .map_err(|_| format!("Invalid argument: {}", argument))?
+            }
+            ("statusline" | "stl", Some(value)) => self.statusline = StatusLine::parse(value)?,
+            ("cursorline" | "cul", None) => self.cursorline = true,
+            ("nocursorline" | "nocul", None) => self.cursorline = false,
+            ("cursorline!" | "cul!" | "invcursorline" | "invcul", None) => {
+                self.cursorline = !self.cursorline
+            }
+            ("colorcolumn" | "cc", Some(value)) => {
+                self.colorcolumn = value
+                    .split(',')
+                    .filter(|column| !column.is_empty())
+                    .map(|column| column.parse::<u16>().ok().filter(|column| *column > 0))
+                    .collect::<Option<Vec<u16>>>()
+                    .ok_or(format!("Invalid argument: {}", argument))?
+            }
+            ("list", None) => self.list = true,
+            ("nolist", None) => self.list = false,
+            ("list!" | "invlist", None) => self.list = !self.list,
+            ("listchars" | "lcs", Some(value)) => self.listchars = ListChars::parse(value)?,
+            ("indentguides", None) => self.indentguides = true,
+            ("noindentguides", None) => self.indentguides = false,
+            ("indentguides!" | "invindentguides", None) => self.indentguides = !self.indentguides,
+            ("minimap", None) => self.minimap = true,
+            ("nominimap", None) => self.minimap = false,
+            ("minimap!" | "invminimap", None) => self.minimap = !self.minimap,
+            ("mouse", None) => self.mouse = true,
+            ("nomouse", None) => self.mouse = false,
+            ("mouse!" | "invmouse", None) => self.mouse = !self.mouse,
+            ("bufferline", None) => self.bufferline = true,
+            ("nobufferline", None) => self.bufferline = false,
+            ("bufferline!" | "invbufferline", None) => self.bufferline = !self.bufferline,
+            ("minimapwidth", Some(value)) => {
+                self.minimapwidth = value
+                    .parse()
+                    .map_err(|_| format!("Invalid argument: {}", argument))?
+            }
+            _ => return Err(format!("Unknown option: {}", argument)),
+        }
+        Ok(())
+    }
+}
+
+/// Options local to a buffer, changed with `:set` or `:setlocal`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BufferOptions {
+    /// Language of the buffer, guessed from the file extension
+    pub filetype: String,
+    /// Line endings written to the file: "unix" or "dos"
+    pub fileformat: String,
+    /// Number of columns of a tab character
+    pub tabstop: u16,
+    /// Insert spaces instead of a tab character
+    pub expandtab: bool,
+}
+
+impl Default for BufferOptions {
+    fn default() -> Self {
+        BufferOptions {
+            filetype: String::new(),
+            fileformat: "unix".to_string(),
+            tabstop: 8,
+            expandtab: false,
+        }
+    }
+}
+
+impl BufferOptions {
+    /// Options of a buffer read from the given file content.
+    pub fn detect(file_path: &str, file_content: &str) -> Self {
+        BufferOptions {
+            filetype: filetype(file_path).to_string(),
+            fileformat: if file_content.contains("\r\n") {
+                "dos".to_string()
+            } else {
+                "unix".to_string()
+            },
+            ..Default::default()
+        }
+    }
+
+    /// Applies a `:set` argument: `name`, `noname`, `name!` or `name=value`.
+    pub fn set(&mut self, argument: &str) -> Result<(), String> {
+        let (name, value) = match argument.split_once('=') {
+            Some((name, value)) => (name, Some(value)),
+            None => (argument, None),
+        };
+        match (name, value) {
+            ("filetype" | "ft", Some(value)) => self.filetype = value.to_string(),
+            ("fileformat" | "ff", Some(value @ ("unix" | "dos"))) => {
+                self.fileformat = value.to_string()
+            }
+            ("tabstop" | "ts", Some(value)) => {
+                self.tabstop = value
+                    .parse()
+                    .ok()
+                    .filter(|tabstop| *tabstop > 0)
+                    .ok_or(format!("Invalid argument: {}", argument))?
+            }
+            ("expandtab" | "et", None) => self.expandtab = true,
+            ("noexpandtab" | "noet", None) => self.expandtab = false,
+            ("expandtab!" | "et!" | "invexpandtab" | "invet", None) => {
+                self.expandtab = !self.expandtab
+            }
+            _ => return Err(format!("Unknown option: {}", argument)),
+        }
+        Ok(())
+    }
+
+    /// Line separator written to the file.
+    pub fn line_ending(&self) -> &'static str {
+        if self.fileformat == "dos" {
+            "\r\n"
+        } else {
+            "\n"
+        }
+    }
+}
+
+/// Guesses the language of a file from its extension.
+fn filetype(file_path: &str) -> &'static str {
+    let extension = Path::new(file_path)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default();
+    match extension {
+        "rs" => "rust",
+        "toml" => "toml",
+        "md" => "markdown",
+        "json" => "json",
+        "yml" | "yaml" => "yaml",
+        "py" => "python",
+        "js" | "mjs" => "javascript",
+        "ts" => "typescript",
+        "c" | "h" => "c",
+        "cpp" | "cc" | "hpp" => "cpp",
+        "go" => "go",
+        "java" => "java",
+        "sh" | "bash" => "sh",
+        "html" => "html",
+        "css" => "css",
+        _ => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn options_should_leave_the_mouse_to_the_terminal_until_set() {
+        let mut options = Options::default();
+        assert!(!options.mouse);
+        options.set("mouse").unwrap();
+        assert!(options.mouse);
+        options.set("nomouse").unwrap();
+        assert!(!options.mouse);
+    }
+
+    #[test]
+    fn buffer_options_should_detect_the_filetype() {
+        let paths = [
+            "src/main.rs",
+            "Cargo.toml",
+            "docs/a.b/notes.md",
+            "Makefile",
+            "x.RS",
+        ];
+        let expect = ["rust", "toml", "markdown", "", ""];
+        for (path, expect) in paths.into_iter().zip(expect) {
+            assert_eq!(BufferOptions::detect(path, "").filetype, expect, "{}", path);
+        }
+    }
+
+    #[test]
+    fn buffer_options_should_keep_the_line_endings_of_the_file() {
+        let contents = ["", "a\nb\n", "a\r\nb\r\n"];
+        let expect = [("unix", "\n"), ("unix", "\n"), ("dos", "\r\n")];
+        for (content, expect) in contents.into_iter().zip(expect) {
+            let options = BufferOptions::detect("a.txt", content);
+            assert_eq!(
+                (options.fileformat.as_str(), options.line_ending()),
+                expect,
+                "{:?}",
+                content
+            );
+        }
+    }
+}
@@ -0,0 +1,26 @@
+use std::{env, path::PathBuf};
+
+/// Directory where the editor keeps its state between runs, following the XDG base directory
+/// specification.
+pub fn state_dir() -> Option<PathBuf> {
+    let state_home = env::var_os("XDG_STATE_HOME")
+        .filter(|state_home| !state_home.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state")))?;
+    Some(state_home.join("oxide"))
+}
+
+/// Directory of the configuration files of the editor, such as its themes.
+pub fn config_dir() -> Option<PathBuf> {
+    let config_home = env::var_os("XDG_CONFIG_HOME")
+        .filter(|config_home| !config_home.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_home.join("oxide"))
+}
+
+/// Session restored when starting without arguments from the given directory.
+pub fn autosession_path(directory: &str) -> Option<PathBuf> {
+    let file_name = directory.replace(['/', '\\', ':'], "%");
+    Some(state_dir()?.join("sessions").join(file_name + ".toml"))
+}
@@ -0,0 +1,262 @@
+use ratatui::{
+    layout::{Position, Rect},
+    text::Line,
+};
+
+use crate::confirm::{Confirm, Question};
+
+/// Identifier of an open popup.
+pub type PopupId = usize;
+
+/// Where a popup is placed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Anchor {
+    /// Centered in the editor
+    Editor,
+    /// Below the cursor of the current view, or above it when there is no room below
+    Cursor,
+    /// Centered in the view of the given index in the current tab page
+    View(usize),
+}
+
+/// Width or height of a popup, borders included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Size {
+    Cells(u16),
+    /// Share of the editor
+    Percent(u16),
+}
+
+impl Size {
+    /// Number of cells out of the total ones.
+    pub fn cells(self, total: u16) -> u16 {
+        match self {
+            Size::Cells(cells) => u16::min(cells, total),
+            Size::Percent(percent) => (total as u32 * u16::min(percent, 100) as u32 / 100) as u16,
+        }
+    }
+}
+
+/// Input typed after `:`, `/` or `?`.
+#[derive(Debug, Clone, Default)]
+pub struct CommandInput {
+    /// What the input is for: ':' for a command, '/' or '?' for a search
+    pub prompt: char,
+    /// Steps back in the history of the displayed entry, if any
+    pub history_index: Option<usize>,
+    /// Command input field
+    pub input_field: String,
+}
+
+/// What a popup shows.
+#[derive(Debug, Clone)]
+pub enum PopupContent {
+    Command(CommandInput),
+    Text(Vec<Line<'static>>),
+    Confirm(Confirm),
+}
+
+/// Floating window drawn over the views.
+#[derive(Debug, Clone)]
+pub struct Popup {
+    /// Set when the popup gets opened
+    pub id: PopupId,
+    pub title: String,
+    pub anchor: Anchor,
+    pub width: Size,
+    pub height: Size,
+    pub content: PopupContent,
+    /// Number of lines of the content scrolled out of the top
+    pub scroll: usize,
+    /// Does the popup take the keys, when it is the topmost one to?
+    pub focusable: bool,
+    /// Popups with a higher index are drawn over the others
+    pub z_index: u16,
+}
+
+impl Popup {
+    pub fn new(
+        title: impl Into<String>,
+        anchor: Anchor,
+        width: Size,
+        height: Size,
+        content: PopupContent,
+    ) -> Self {
+        Popup {
+            id: 0,
+            title: title.into(),
+            anchor,
+            width,
+            height,
+            content,
+            scroll: 0,
+            focusable: true,
+            z_index: 0,
+        }
+    }
+
+    /// Area of the popup in the editor, from the terminal position of the cursor and the areas of
+    /// the views.
+    pub fn area(&self, bounds: Rect, cursor: Position, views_areas: &[Rect]) -> Rect {
+        let width = self.width.cells(bounds.width);
+        let height = self.height.cells(bounds.height);
+        let centered = |area: Rect| Rect {
+            x: area.x + area.width.saturating_sub(width) / 2,
+            y: area.y + area.height.saturating_sub(height) / 2,
+            width,
+            height,
+        };
+        let area = match self.anchor {
+            Anchor::Editor => centered(bounds),
+            Anchor::View(index) => centered(views_areas.get(index).copied().unwrap_or(bounds)),
+            Anchor::Cursor => {
+                let y = if cursor.y + 1 + height <= bounds.bottom() {
+                    cursor.y + 1
+                } else {
+                    cursor.y.saturating_sub(height).max(bounds.y)
+                };
+                let x = u16::min(cursor.x, bounds.right().saturating_sub(width));
+                Rect {
+                    x,
+                    y,
+                    width,
+                    height,
+                }
+            }
+        };
+        area.intersection(bounds)
+    }
+
+    /// Number of lines of the content.
+    pub fn lines_number(&self) -> usize {
+        match &self.content {
+            PopupContent::Command(_) => 1,
+            PopupContent::Text(lines) => lines.len(),
+            PopupContent::Confirm(confirm) => confirm.lines().len(),
+        }
+    }
+}
+
+/// Open popups, from the bottom one to the topmost one.
+#[derive(Debug, Default)]
+pub struct Popups {
+    list: Vec<Popup>,
+    next_id: PopupId,
+}
+
+impl Popups {
+    /// Shows a popup over the ones of the same or a lower z-index, and returns its identifier.
+    pub fn open(&mut self, mut popup: Popup) -> PopupId {
+        self.next_id += 1;
+        popup.id = self.next_id;
+        let index = self
+            .list
+            .iter()
+            .position(|open| open.z_index > popup.z_index)
+            .unwrap_or(self.list.len());
+        self.list.insert(index, popup);
+        self.next_id
+    }
+
+    pub fn close(&mut self, id: PopupId) {
+        self.list.retain(|popup| popup.id != id);
+    }
+
+    /// Closes the popup taking the keys.
+    pub fn close_focused(&mut self) {
+        if let Some(id) = self.focused().map(|popup| popup.id) {
+            self.close(id);
+        }
+    }
+
+    /// Popups in the order they are drawn.
+    pub fn iter(&self) -> impl Iterator<Item = &Popup> {
+        self.list.iter()
+    }
+
+    /// Topmost popup taking the keys.
+    pub fn focused(&self) -> Option<&Popup> {
+        self.list.iter().rev().find(|popup| popup.focusable)
+    }
+
+    pub fn focused_mut(&mut self) -> Option<&mut Popup> {
+        self.list.iter_mut().rev().find(|popup| popup.focusable)
+    }
+
+    /// Is a confirm dialog asking the question still open?
+    pub fn asks(&self, question: Question) -> bool {
+        self.list.iter().any(|popup| {
+            matches!(&popup.content, PopupContent::Confirm(confirm) if confirm.question == question)
+        })
+    }
+
+    /// Input of the focused popup, when it is the command line.
+    pub fn command_input(&self) -> Option<&CommandInput> {
+        match &self.focused()?.content {
+            PopupContent::Command(command_input) => Some(command_input),
+            _ => None,
+        }
+    }
+
+    pub fn command_input_mut(&mut self) -> Option<&mut CommandInput> {
+        match &mut self.focused_mut()?.content {
+            PopupContent::Command(command_input) => Some(command_input),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn popups_should_stack_and_be_placed_at_their_anchor() {
+        let text = |z_index, focusable| Popup {
+            z_index,
+            focusable,
+            ..Popup::new(
+                "",
+                Anchor::Editor,
+                Size::Percent(50),
+                Size::Cells(4),
+                PopupContent::Text(vec![]),
+            )
+        };
+        let mut popups = Popups::default();
+        let high = popups.open(text(1, true));
+        let low = popups.open(text(0, true));
+        let hint = popups.open(text(2, false));
+        let order: Vec<PopupId> = popups.iter().map(|popup| popup.id).collect();
+        assert_eq!(order, [low, high, hint]);
+        assert_eq!(popups.focused().map(|popup| popup.id), Some(high));
+        popups.close_focused();
+        assert_eq!(popups.focused().map(|popup| popup.id), Some(low));
+
+        let bounds = Rect::new(0, 0, 80, 20);
+        let views = [Rect::new(0, 0, 40, 20), Rect::new(41, 0, 39, 20)];
+        let area = |anchor, cursor| {
+            Popup {
+                anchor,
+                ..text(0, true)
+            }
+            .area(bounds, cursor, &views)
+        };
+        assert_eq!(
+            area(Anchor::Editor, Position::new(0, 0)),
+            Rect::new(20, 8, 40, 4)
+        );
+        assert_eq!(
+            area(Anchor::View(1), Position::new(0, 0)),
+            Rect::new(41, 8, 39, 4)
+        );
+        assert_eq!(
+            area(Anchor::Cursor, Position::new(5, 3)),
+            Rect::new(5, 4, 40, 4)
+        );
+        assert_eq!(
+            area(Anchor::Cursor, Position::new(70, 18)),
+            Rect::new(40, 14, 40, 4)
+        );
+    }
+}
@@ -0,0 +1,62 @@
+use std::collections::BTreeMap;
+
+/// Register written when no register is selected.
+pub const UNNAMED_REGISTER: char = '"';
+
+/// Text yanked or deleted.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct Register {
+    pub text: Vec<Vec<char>>,
+    /// Is the text made of whole lines?
+    pub linewise: bool,
+}
+
+/// Registers, by name.
+#[derive(Default, Debug, Clone)]
+pub struct Registers {
+    pub registers: BTreeMap<char, Register>,
+}
+
+impl Registers {
+    /// Can the register be selected with `"`?
+    pub fn is_valid(name: char) -> bool {
+        name == UNNAMED_REGISTER || name.is_ascii_lowercase()
+    }
+
+    pub fn get(&self, name: Option<char>) -> Option<&Register> {
+        self.registers.get(&name.unwrap_or(UNNAMED_REGISTER))
+    }
+
+    /// Writes the selected register. The unnamed register always holds the last written text.
+    pub fn set(&mut self, name: Option<char>, register: Register) {
+        if let Some(name) = name.filter(|name| *name != UNNAMED_REGISTER) {
+            self.registers.insert(name, register.clone());
+        }
+        self.registers.insert(UNNAMED_REGISTER, register);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registers_should_keep_the_last_text_in_the_unnamed_one() {
+        let register = |text: &str| Register {
+            text: vec![text.chars().collect()],
+            linewise: false,
+        };
+        let mut registers = Registers::default();
+        registers.set(None, register("one"));
+        registers.set(Some('a'), register("two"));
+        assert_eq!(registers.get(Some('a')), Some(&register("two")));
+        assert_eq!(registers.get(None), Some(&register("two")));
+        registers.set(Some(UNNAMED_REGISTER), register("three"));
+        assert_eq!(registers.get(Some('a')), Some(&register("two")));
+        assert_eq!(registers.get(None), Some(&register("three")));
+        assert_eq!(registers.get(Some('b')), None);
+
+        assert!(Registers::is_valid('"') && Registers::is_valid('z'));
+        assert!(!Registers::is_valid('A') && !Registers::is_valid('1'));
+    }
+}
@@ -0,0 +1,279 @@
+use std::{collections::HashMap, env, fs, io, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::Editor,
+    filesbuffers::{BufferId, BufferKind, FileBuffer},
+    options::BufferOptions,
+    tabpage::{TabPage, TabPages},
+    view::{Split, View, Views},
+};
+
+/// Buffers and layout of the editor, saved with `:mksession` and restored with `-S`.
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    /// Current directory
+    pub directory: Option<String>,
+    pub buffers: Vec<SessionBuffer>,
+    pub tabs: Vec<SessionTab>,
+    /// Index of the displayed tab page
+    pub current_tab: usize,
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionBuffer {
+    pub id: BufferId,
+    pub kind: BufferKind,
+    pub file_path: Option<String>,
+    pub options: BufferOptions,
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionTab {
+    pub split: Split,
+    /// Index of the focused view
+    pub current_view: usize,
+    pub views: Vec<SessionView>,
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionView {
+    pub buffer_id: BufferId,
+    pub line: u16,
+    pub column: u16,
+    pub scroll_y: u16,
+}
+
+impl Session {
+    /// Captures the buffers and layout of the editor.
+    pub fn capture(editor: &Editor) -> Self {
+        let directory = env::current_dir()
+            .ok()
+            .map(|directory| directory.to_string_lossy().to_string());
+        let buffers = editor
+            .buffers
+            .files
+            .iter()
+            .map(|(id, file_buffer)| SessionBuffer {
+                id: *id,
+                kind: file_buffer.kind,
+                file_path: file_buffer.file_path.clone(),
+                options: file_buffer.options.clone(),
+            })
+            .collect();
+        let tabs = editor
+            .tabs
+            .list
+            .iter()
+            .map(|tab_page| SessionTab {
+                split: tab_page.views.split,
+                current_view: tab_page.views.current,
+                views: tab_page
+                    .views
+                    .list
+                    .iter()
+                    .map(|view| SessionView {
+                        buffer_id: view.buffer_id,
+                        line: view.current_line,
+                        column: view.current_column,
+                        scroll_y: view.scroll_y,
+                    })
+                    .collect(),
+            })
+            .collect();
+        Session {
+            directory,
+            buffers,
+            tabs,
+            current_tab: editor.tabs.current,
+        }
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let content = fs::read_to_string(path)?;
+        toml::from_str(&content).map_err(io::Error::other)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let content = toml::to_string(self).map_err(io::Error::other)?;
+        fs::write(path, content)
+    }
+
+    /// Replaces the layout of the editor with the session one, opening its buffers.
+    ///
+    /// Buffers without a file are restored empty.
+    pub fn restore(self, editor: &mut Editor) {
+        if let Some(directory) = &self.directory
+            && let Err(e) = env::set_current_dir(directory)
+        {
+            editor
+                .messages
+                .error(format!("Can't change directory to {}: {}", directory, e));
+        }
+        let mut buffer_ids = HashMap::new();
+        for session_buffer in self.buffers {
+            let buffer_id = match &session_buffer.file_path {
+                Some(file_path) => editor.open_buffer(file_path),
+                None => Some(editor.buffers.create(FileBuffer::new(
+                    session_buffer.kind,
+                    None,
+                    vec![],
+                ))),
+            };
+            // A file that can't be read anymore was reported, its views are dropped
+            let Some(buffer_id) = buffer_id else {
+                continue;
+            };
+            editor.buffers.get_mut(buffer_id).options = session_buffer.options;
+            buffer_ids.insert(session_buffer.id, buffer_id);
+        }
+        let mut tabs = vec![];
+        for session_tab in self.tabs {
+            let mut views = vec![];
+            for session_view in session_tab.views {
+                let Some(buffer_id) = buffer_ids.get(&session_view.buffer_id) else {
+                    continue;
+                };
+                let mut view = View::new(*buffer_id);
+                view.current_line = session_view.line;
+                view.current_column = session_view.column;
+                view.scroll_y = session_view.scroll_y;
+                let file_buffer = editor.buffers.get_mut(*buffer_id);
+                view.clamp(file_buffer);
+                view.remember_column(file_buffer);
+                views.push(view);
+            }
+            if views.is_empty() {
+                continue;
+            }
+            tabs.push(TabPage {
+                views: Views {
+                    current: usize::min(session_tab.current_view, views.len() - 1),
+                    list: views,
+                    split: session_tab.split,
+                    sizes: None,
+                },
+            });
+        }
+        if tabs.is_empty() {
+            return;
+        }
+        editor.tabs = TabPages {
+            current: usize::min(self.current_tab, tabs.len() - 1),
+            list: tabs,
+        };
+        // Drops the unnamed buffer opened before the session was restored
+        editor.retain_buffers(|id, file_buffer| {
+            file_buffer.file_path.is_some() || buffer_ids.values().any(|buffer_id| *buffer_id == id)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Split, focused view and cursor of the views of every tab page, with the current one.
+    type Layout = (usize, Vec<(Split, usize, Vec<(Option<String>, u16, u16)>)>);
+
+    fn layout(editor: &Editor) -> Layout {
+        let tabs = editor
+            .tabs
+            .list
+            .iter()
+            .map(|tab_page| {
+                let views = tab_page
+                    .views
+                    .list
+                    .iter()
+                    .map(|view| {
+                        let file_path = editor.buffers.get(view.buffer_id).file_path.clone();
+                        (file_path, view.current_line, view.current_column)
+                    })
+                    .collect();
+                (tab_page.views.split, tab_page.views.current, views)
+            })
+            .collect();
+        (editor.tabs.current, tabs)
+    }
+
+    #[test]
+    fn session_should_restore_what_was_captured() {
+        let directory = env::temp_dir().join(format!("oxide-session-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let path = |name: &str| directory.join(name).to_string_lossy().to_string();
+        fs::write(path("a.rs"), "fn main() {}\n\n    more\n").unwrap();
+        fs::write(path("b.txt"), "b\n").unwrap();
+
+        let mut editor = Editor::with_lines(&[]);
+        let a = editor.open_buffer(&path("a.rs")).unwrap();
+        let b = editor.open_buffer(&path("b.txt")).unwrap();
+        editor.buffers.get_mut(a).options.tabstop = 2;
+        let mut view = View::new(a);
+        (view.current_line, view.current_column) = (2, 4);
+        editor.tabs.current_mut().views = Views {
+            list: vec![view, View::new(b)],
+            current: 1,
+            split: Split::Vertical,
+            sizes: None,
+        };
+        editor.tabs.open(TabPage::new(View::new(b)));
+        let session_path = directory.join("session.toml");
+        Session::capture(&editor).save(&session_path).unwrap();
+
+        let mut restored = Editor::with_lines(&[]);
+        Session::load(&session_path).unwrap().restore(&mut restored);
+        fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(layout(&restored), layout(&editor));
+        assert_eq!(restored.buffers.files.len(), editor.buffers.files.len());
+        let file_path = Some(path("a.rs"));
+        let options = restored
+            .buffers
+            .files
+            .values()
+            .find(|file_buffer| file_buffer.file_path == file_path)
+            .map(|file_buffer| file_buffer.options.clone());
+        assert_eq!(options, Some(editor.buffers.get(a).options.clone()));
+    }
+
+    #[test]
+    fn session_restore_should_drop_the_startup_buffer_from_the_bufferline() {
+        let mut editor = Editor::with_lines(&[]);
+        // Marks and selection on the startup buffer go with it
+        let startup = editor.tabs.current().views.current().location();
+        editor.global_marks.insert('A', startup);
+        editor.selection_start = Some(startup);
+        let session = Session {
+            buffers: vec![SessionBuffer {
+                id: 7,
+                kind: BufferKind::Scratch,
+                ..SessionBuffer::default()
+            }],
+            tabs: vec![SessionTab {
+                views: vec![SessionView {
+                    buffer_id: 7,
+                    ..SessionView::default()
+                }],
+                ..SessionTab::default()
+            }],
+            ..Session::default()
+        };
+        session.restore(&mut editor);
+
+        let listed = editor.buffers.listed();
+        assert_eq!(listed.len(), 1);
+        assert!(editor.global_marks.is_empty());
+        assert_eq!(editor.selection_start, None);
+        assert_eq!(editor.buffers.order, listed);
+        assert_eq!(editor.tabs.current().views.current().buffer_id, listed[0]);
+    }
+}
@@ -0,0 +1,95 @@
+use ratatui::style::Style;
+
+use crate::filesbuffers::Edit;
+
+/// Number of cells of the sign column.
+pub const SIGN_COLUMN_WIDTH: u16 = 2;
+/// Namespace of the signs of diagnostics, whose text tells their severity ("E", "W", ...).
+pub const DIAGNOSTICS_NAMESPACE: &str = "diagnostics";
+
+/// Marker shown in the sign column, beside the number of a line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sign {
+    /// Subsystem which placed the sign, and clears all of its signs at once
+    pub namespace: &'static str,
+    pub line: u16,
+    /// One or two cells of text
+    pub text: String,
+    pub style: Style,
+    /// When several signs share a line, the one with the highest priority is shown
+    pub priority: u16,
+}
+
+/// Signs placed in a buffer.
+#[derive(Default, Debug, Clone)]
+pub struct Signs {
+    list: Vec<Sign>,
+}
+
+impl Signs {
+    pub fn place(&mut self, sign: Sign) {
+        self.list.push(sign);
+    }
+
+    /// Removes all the signs of a namespace.
+    pub fn clear(&mut self, namespace: &str) {
+        self.list.retain(|sign| sign.namespace != namespace);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Sign> {
+        self.list.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    /// Sign shown on a line: the one with the highest priority, the latest placed if tied.
+    pub fn get(&self, line: u16) -> Option<&Sign> {
+        self.list
+            .iter()
+            .filter(|sign| sign.line == line)
+            .max_by_key(|sign| sign.priority)
+    }
+
+    /// Keeps the signs on their lines after an edit, removing the ones of removed lines.
+    pub fn apply_edit(&mut self, edit: Edit) {
+        self.list.retain_mut(|sign| {
+            let mut column = 0;
+            edit.shift(&mut sign.line, &mut column) || !matches!(edit, Edit::RemoveLines { .. })
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sign(namespace: &'static str, line: u16, priority: u16) -> Sign {
+        Sign {
+            namespace,
+            line,
+            text: namespace.to_string(),
+            style: Style::default(),
+            priority,
+        }
+    }
+
+    #[test]
+    fn signs_should_follow_edits() {
+        let mut signs = Signs::default();
+        signs.place(sign("a", 1, 10));
+        signs.place(sign("b", 1, 20));
+        signs.place(sign("a", 3, 10));
+        signs.place(sign("a", 5, 10));
+        signs.apply_edit(Edit::InsertLines { line: 0, count: 2 });
+        signs.apply_edit(Edit::RemoveLines { line: 5, count: 1 });
+
+        assert_eq!(signs.get(3).map(|sign| sign.namespace), Some("b"));
+        assert_eq!(signs.get(5), None);
+        assert_eq!(signs.get(6).map(|sign| sign.namespace), Some("a"));
+
+        signs.clear("b");
+        assert_eq!(signs.get(3).map(|sign| sign.namespace), Some("a"));
+    }
+}
@@ -0,0 +1,102 @@
+use std::path::Path;
+
+use crate::{session::Session, state::State};
+
+/// Number of recent files listed.
+const MAX_FILES: usize = 6;
+/// Number of recent sessions listed.
+const MAX_SESSIONS: usize = 3;
+
+/// What a start screen entry opens.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StartTarget {
+    File(String),
+    Session(String),
+}
+
+/// Line of the start screen, opened by typing its key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StartEntry {
+    pub key: char,
+    pub label: String,
+    pub target: StartTarget,
+}
+
+/// Screen shown instead of the empty buffer when starting without arguments, until the first
+/// edit.
+#[derive(Default, Debug, Clone)]
+pub struct StartScreen {
+    /// Recent files first, then recent sessions, numbered from 1
+    pub entries: Vec<StartEntry>,
+}
+
+impl StartScreen {
+    /// Lists the most recent files and sessions that still exist.
+    pub fn new(state: &State) -> Self {
+        let files = (0..)
+            .map_while(|index| state.recent_files.get(index))
+            .filter(|file_path| Path::new(file_path).is_file())
+            .take(MAX_FILES)
+            .map(|file_path| {
+                (
+                    file_path.to_string(),
+                    StartTarget::File(file_path.to_string()),
+                )
+            });
+        let sessions = (0..)
+            .map_while(|index| state.recent_sessions.get(index))
+            .filter_map(|session_path| {
+                let session = Session::load(Path::new(session_path)).ok()?;
+                Some((
+                    session.directory.unwrap_or(session_path.to_string()),
+                    StartTarget::Session(session_path.to_string()),
+                ))
+            })
+            .take(MAX_SESSIONS);
+        let entries = files
+            .chain(sessions)
+            .zip('1'..='9')
+            .map(|((label, target), key)| StartEntry { key, label, target })
+            .collect();
+        StartScreen { entries }
+    }
+
+    pub fn entry(&self, key: char) -> Option<&StartEntry> {
+        self.entries.iter().find(|entry| entry.key == key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use super::*;
+
+    #[test]
+    fn start_screen_should_number_the_recent_files_that_still_exist() {
+        let directory = env::temp_dir().join(format!("oxide-start-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let path = |index: usize| directory.join(format!("{}.txt", index));
+        let mut state = State::default();
+        for index in 0..9 {
+            if index != 7 {
+                fs::write(path(index), "text").unwrap();
+            }
+            state.recent_files.push(&path(index).to_string_lossy());
+        }
+        let screen = StartScreen::new(&state);
+        fs::remove_dir_all(&directory).unwrap();
+
+        let entries: Vec<(char, StartTarget)> = screen
+            .entries
+            .into_iter()
+            .map(|entry| (entry.key, entry.target))
+            .collect();
+        let expect: Vec<(char, StartTarget)> = [8, 6, 5, 4, 3, 2]
+            .into_iter()
+            .zip('1'..)
+            .map(|(index, key)| (key, StartTarget::File(path(index).to_string_lossy().into())))
+            .collect();
+        assert_eq!(entries, expect);
+    }
+}
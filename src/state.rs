@@ -0,0 +1,260 @@
+use std::{
+    collections::BTreeMap,
+    fs::{self, OpenOptions},
+    io,
+    path::{Path, PathBuf},
+    process, thread,
+    time::{Duration, SystemTime},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{history::History, paths::state_dir};
+
+/// Maximum number of files whose last cursor position is remembered.
+const MAX_POSITIONS: usize = 200;
+/// Longest wait for another instance to finish saving the state.
+const LOCK_TIMEOUT: Duration = Duration::from_secs(2);
+/// Age of a lock left behind by an instance that died while saving.
+const STALE_LOCK: Duration = Duration::from_secs(10);
+
+/// Editor state kept between runs.
+///
+/// Every instance merges its state with the saved one when quitting, the most recent value of
+/// each entry winning.
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct State {
+    pub command_history: History,
+    pub search_history: History,
+    /// Paths of the files opened, most recent last
+    pub recent_files: History,
+    /// Paths of the sessions restored or saved, most recent last
+    pub recent_sessions: History,
+    /// Registers, by name
+    pub registers: BTreeMap<String, StateRegister>,
+    /// File marks, by name
+    pub marks: BTreeMap<String, StateMark>,
+    /// Last cursor position, by file path
+    pub positions: BTreeMap<String, StatePosition>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StateRegister {
+    pub text: Vec<String>,
+    pub linewise: bool,
+    /// Unix timestamp of the last change
+    pub time: i64,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StateMark {
+    pub file_path: String,
+    pub line: u16,
+    pub column: u16,
+    /// Unix timestamp of the last change
+    pub time: i64,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StatePosition {
+    pub line: u16,
+    pub column: u16,
+    /// Unix timestamp of the last change
+    pub time: i64,
+}
+
+impl State {
+    pub fn path() -> Option<PathBuf> {
+        Some(state_dir()?.join("state.toml"))
+    }
+
+    /// Loads the saved state, an empty one if there is none.
+    pub fn load() -> io::Result<Self> {
+        match Self::path() {
+            Some(path) => Self::load_from(&path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Merges the state with the one saved meanwhile by other instances, then saves it.
+    pub fn save(&self) -> io::Result<()> {
+        let path = Self::path().ok_or(io::Error::other("No state directory"))?;
+        self.save_to(&path)
+    }
+
+    fn load_from(path: &Path) -> io::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(content) => {
+                toml::from_str(&content).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Saves the state to a file, holding its lock while merging so that instances quitting
+    /// together don't lose each other's entries. A saved state that can't be parsed is kept
+    /// aside rather than overwritten.
+    fn save_to(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let _lock = Lock::acquire(&path.with_extension("lock"))?;
+        let mut state = match Self::load_from(path) {
+            Ok(state) => state,
+            Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+                fs::rename(path, path.with_extension("toml.corrupt"))?;
+                Self::default()
+            }
+            Err(e) => return Err(e),
+        };
+        state.merge(self.clone());
+        // Writes then renames so that other instances never read a partial file
+        let temporary_path = path.with_extension(format!("{}.tmp", process::id()));
+        let content = toml::to_string(&state).map_err(io::Error::other)?;
+        fs::write(&temporary_path, content)?;
+        fs::rename(&temporary_path, path)
+    }
+
+    /// Adds the entries of another state, keeping the most recent value of each one.
+    pub fn merge(&mut self, other: State) {
+        self.command_history.merge(other.command_history);
+        self.search_history.merge(other.search_history);
+        self.recent_files.merge(other.recent_files);
+        self.recent_sessions.merge(other.recent_sessions);
+        merge_newest(&mut self.registers, other.registers, |register| {
+            register.time
+        });
+        merge_newest(&mut self.marks, other.marks, |mark| mark.time);
+        merge_newest(&mut self.positions, other.positions, |position| {
+            position.time
+        });
+        while self.positions.len() > MAX_POSITIONS {
+            let oldest = self
+                .positions
+                .iter()
+                .min_by_key(|(_, position)| position.time)
+                .map(|(file_path, _)| file_path.clone());
+            if let Some(oldest) = oldest {
+                self.positions.remove(&oldest);
+            }
+        }
+    }
+}
+
+/// File existing while an instance saves the state, removed when dropped.
+struct Lock {
+    path: PathBuf,
+}
+
+impl Lock {
+    /// Creates the lock file, waiting for the instance holding it to remove it.
+    fn acquire(path: &Path) -> io::Result<Self> {
+        let start = SystemTime::now();
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(path) {
+                Ok(_) => {
+                    return Ok(Lock {
+                        path: path.to_path_buf(),
+                    });
+                }
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {}
+                Err(e) => return Err(e),
+            }
+            let is_stale = fs::metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .is_ok_and(|modified| modified.elapsed().unwrap_or_default() >= STALE_LOCK);
+            if is_stale {
+                let _ = fs::remove_file(path);
+            } else if start.elapsed().unwrap_or_default() >= LOCK_TIMEOUT {
+                return Err(io::Error::new(
+                    io::ErrorKind::WouldBlock,
+                    format!("State file locked by {}", path.display()),
+                ));
+            } else {
+                thread::sleep(Duration::from_millis(10));
+            }
+        }
+    }
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+fn merge_newest<T>(
+    entries: &mut BTreeMap<String, T>,
+    other: BTreeMap<String, T>,
+    time: fn(&T) -> i64,
+) {
+    for (key, entry) in other {
+        if entries
+            .get(&key)
+            .is_none_or(|known| time(known) <= time(&entry))
+        {
+            entries.insert(key, entry);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(line: u16, time: i64) -> StatePosition {
+        StatePosition {
+            line,
+            column: 0,
+            time,
+        }
+    }
+
+    #[test]
+    fn state_merge_should_keep_most_recent_entries() {
+        let mut saved = State::default();
+        saved.positions.insert("a".to_string(), position(1, 10));
+        saved.positions.insert("b".to_string(), position(2, 30));
+        let mut other = State::default();
+        other.positions.insert("a".to_string(), position(5, 20));
+        other.positions.insert("b".to_string(), position(6, 20));
+        other.positions.insert("c".to_string(), position(7, 20));
+
+        saved.merge(other);
+
+        assert_eq!(saved.positions["a"], position(5, 20));
+        assert_eq!(saved.positions["b"], position(2, 30));
+        assert_eq!(saved.positions["c"], position(7, 20));
+    }
+
+    #[test]
+    fn state_save_should_merge_with_the_saved_state_and_keep_a_corrupt_one() {
+        let directory = std::env::temp_dir().join(format!("oxide-state-{}", process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("state.toml");
+        fs::write(&path, "positions = [").unwrap();
+
+        let mut first = State::default();
+        first.positions.insert("a".to_string(), position(1, 10));
+        let saved = first.save_to(&path);
+        let mut second = State::default();
+        second.positions.insert("b".to_string(), position(2, 20));
+        let saved = saved.and_then(|()| second.save_to(&path));
+        let loaded = State::load_from(&path);
+        let corrupt = fs::read_to_string(directory.join("state.toml.corrupt"));
+        let locked = directory.join("state.lock").exists();
+        fs::remove_dir_all(&directory).unwrap();
+
+        saved.unwrap();
+        let loaded = loaded.unwrap();
+        assert_eq!(loaded.positions["a"], position(1, 10));
+        assert_eq!(loaded.positions["b"], position(2, 20));
+        assert_eq!(corrupt.unwrap(), "positions = [");
+        assert!(!locked);
+    }
+}
@@ -0,0 +1,124 @@
+/// Piece of information shown in the statusline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Segment {
+    /// Editor mode, coloured after it
+    Mode,
+    FileName,
+    /// `[+]` when the buffer has changes not written yet
+    Modified,
+    FileType,
+    Encoding,
+    /// Line endings
+    FileFormat,
+    /// Cursor line and column
+    Position,
+    /// Position of the cursor line in the buffer
+    Percentage,
+    /// Size of the visual selection
+    Selection,
+    /// Count, register and keys typed so far for a command
+    PendingKeys,
+    /// Register a macro is being recorded into
+    Recording,
+    /// Number of diagnostics by severity
+    Diagnostics,
+}
+
+impl Segment {
+    pub fn from_name(name: &str) -> Option<Segment> {
+        match name {
+            "mode" => Some(Segment::Mode),
+            "filename" => Some(Segment::FileName),
+            "modified" => Some(Segment::Modified),
+            "filetype" => Some(Segment::FileType),
+            "encoding" => Some(Segment::Encoding),
+            "fileformat" => Some(Segment::FileFormat),
+            "position" => Some(Segment::Position),
+            "percentage" => Some(Segment::Percentage),
+            "selection" => Some(Segment::Selection),
+            "pending" => Some(Segment::PendingKeys),
+            "recording" => Some(Segment::Recording),
+            "diagnostics" => Some(Segment::Diagnostics),
+            _ => None,
+        }
+    }
+}
+
+/// Segments of the statusline, by alignment.
+///
+/// When the line is too narrow, the centre goes first, then the right segments from the left
+/// one, and the left segments get truncated last.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusLine {
+    pub left: Vec<Segment>,
+    pub center: Vec<Segment>,
+    pub right: Vec<Segment>,
+}
+
+impl Default for StatusLine {
+    fn default() -> Self {
+        StatusLine {
+            left: vec![Segment::Mode, Segment::FileName, Segment::Modified],
+            center: vec![Segment::PendingKeys, Segment::Recording],
+            right: vec![
+                Segment::Diagnostics,
+                Segment::Selection,
+                Segment::FileType,
+                Segment::Encoding,
+                Segment::FileFormat,
+                Segment::Position,
+                Segment::Percentage,
+            ],
+        }
+    }
+}
+
+impl StatusLine {
+    /// Parses the `statusline` option: segment names separated by commas, the left, centre and
+    /// right groups separated by `|`.
+    pub fn parse(value: &str) -> Result<StatusLine, String> {
+        let mut groups = value.split('|').map(|group| {
+            group
+                .split(',')
+                .filter(|name| !name.is_empty())
+                .map(|name| Segment::from_name(name).ok_or(format!("Unknown segment: {}", name)))
+                .collect::<Result<Vec<Segment>, String>>()
+        });
+        let statusline = StatusLine {
+            left: groups.next().transpose()?.unwrap_or_default(),
+            center: groups.next().transpose()?.unwrap_or_default(),
+            right: groups.next().transpose()?.unwrap_or_default(),
+        };
+        if groups.next().is_some() {
+            return Err(format!("Too many groups: {}", value));
+        }
+        Ok(statusline)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn statusline_should_parse_groups() {
+        assert_eq!(
+            StatusLine::parse("mode,filename||position"),
+            Ok(StatusLine {
+                left: vec![Segment::Mode, Segment::FileName],
+                center: vec![],
+                right: vec![Segment::Position],
+            })
+        );
+        assert_eq!(
+            StatusLine::parse("mode|pending"),
+            Ok(StatusLine {
+                left: vec![Segment::Mode],
+                center: vec![Segment::PendingKeys],
+                right: vec![],
+            })
+        );
+        assert!(StatusLine::parse("mode,unknown").is_err());
+        assert!(StatusLine::parse("mode|||position").is_err());
+    }
+}
@@ -0,0 +1,111 @@
+use crate::{
+    filesbuffers::FilesBuffers,
+    view::{View, Views},
+};
+
+/// Tab page, holding its own views layout.
+#[derive(Default, Debug, Clone)]
+pub struct TabPage {
+    pub views: Views,
+}
+
+/// Tab pages of the editor.
+#[derive(Default, Debug, Clone)]
+pub struct TabPages {
+    pub list: Vec<TabPage>,
+    /// Index of the displayed tab page
+    pub current: usize,
+}
+
+impl TabPage {
+    pub fn new(view: View) -> Self {
+        TabPage {
+            views: Views::new(view),
+        }
+    }
+
+    /// Label shown in the tabline: the name of the buffer of the focused view.
+    pub fn label(&self, buffers: &FilesBuffers) -> String {
+        buffers.files[&self.views.current().buffer_id].name()
+    }
+}
+
+impl TabPages {
+    pub fn new(tab_page: TabPage) -> Self {
+        TabPages {
+            list: vec![tab_page],
+            current: 0,
+        }
+    }
+
+    pub fn current(&self) -> &TabPage {
+        &self.list[self.current]
+    }
+
+    pub fn current_mut(&mut self) -> &mut TabPage {
+        &mut self.list[self.current]
+    }
+
+    /// Opens a tab page after the current one and displays it.
+    pub fn open(&mut self, tab_page: TabPage) {
+        self.current += 1;
+        self.list.insert(self.current, tab_page);
+    }
+
+    /// Closes the current tab page. The last tab page can't be closed.
+    pub fn close(&mut self) -> bool {
+        if self.list.len() < 2 {
+            return false;
+        }
+        self.list.remove(self.current);
+        self.current = usize::min(self.current, self.list.len() - 1);
+        true
+    }
+
+    pub fn next(&mut self) {
+        self.current = (self.current + 1) % self.list.len();
+    }
+
+    pub fn previous(&mut self) {
+        self.current = (self.current + self.list.len() - 1) % self.list.len();
+    }
+
+    /// Iterates over the views of every tab page.
+    pub fn views_mut(&mut self) -> impl Iterator<Item = &mut View> {
+        self.list
+            .iter_mut()
+            .flat_map(|tab_page| tab_page.views.list.iter_mut())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tab_pages_should_open_after_the_current_one_and_keep_the_last_one() {
+        let buffer_id = |tabs: &TabPages| tabs.current().views.current().buffer_id;
+        let mut tabs = TabPages::new(TabPage::new(View::new(0)));
+        assert!(!tabs.close());
+        tabs.open(TabPage::new(View::new(1)));
+        tabs.previous();
+        tabs.open(TabPage::new(View::new(2)));
+        let order: Vec<_> = tabs
+            .list
+            .iter()
+            .map(|tab| tab.views.current().buffer_id)
+            .collect();
+        assert_eq!(order, [0, 2, 1]);
+        assert_eq!(buffer_id(&tabs), 2);
+        tabs.next();
+        tabs.next();
+        assert_eq!(buffer_id(&tabs), 0);
+        tabs.previous();
+        assert_eq!(buffer_id(&tabs), 1);
+        assert!(tabs.close());
+        assert_eq!(buffer_id(&tabs), 2);
+        assert!(tabs.close());
+        assert!(!tabs.close());
+        assert_eq!(buffer_id(&tabs), 0);
+    }
+}
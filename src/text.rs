@@ -0,0 +1,144 @@
+use std::ops::Range;
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Ranges of characters of the extended grapheme clusters of a line.
+pub fn graphemes(line: &[char]) -> Vec<Range<usize>> {
+    // Every ASCII character but a carriage return is a grapheme of its own
+    if line.iter().all(|c| c.is_ascii() && *c != '\r') {
+        return (0..line.len()).map(|start| start..start + 1).collect();
+    }
+    let text: String = line.iter().collect();
+    let mut start = 0;
+    text.graphemes(true)
+        .map(|grapheme| {
+            let end = start + grapheme.chars().count();
+            let range = start..end;
+            start = end;
+            range
+        })
+        .collect()
+}
+
+/// Start of the grapheme holding the column, the end of the line when past it.
+pub fn boundary(line: &[char], column: u16) -> u16 {
+    graphemes(line)
+        .into_iter()
+        .find(|grapheme| grapheme.end > column as usize)
+        .map_or(line.len(), |grapheme| grapheme.start) as u16
+}
+
+/// Start of the grapheme following the one holding the column, the end of the line at most.
+pub fn next_boundary(line: &[char], column: u16) -> u16 {
+    graphemes(line)
+        .into_iter()
+        .find(|grapheme| grapheme.start > column as usize)
+        .map_or(line.len(), |grapheme| grapheme.start) as u16
+}
+
+/// Start of the grapheme preceding the column, the start of the line at least.
+pub fn previous_boundary(line: &[char], column: u16) -> u16 {
+    graphemes(line)
+        .into_iter()
+        .rfind(|grapheme| grapheme.start < column as usize)
+        .map_or(0, |grapheme| grapheme.start) as u16
+}
+
+/// Number of cells of a grapheme displayed at the given display column. Tabs stretch up to the
+/// next multiple of `tabstop`.
+pub fn grapheme_width(grapheme: &[char], display_column: usize, tabstop: u16) -> usize {
+    match grapheme {
+        ['\t'] => {
+            let tabstop = usize::max(tabstop as usize, 1);
+            tabstop - display_column % tabstop
+        }
+        [c] => c.width().unwrap_or(0),
+        _ => grapheme.iter().collect::<String>().width(),
+    }
+}
+
+/// Display columns of every column of a line and of its end, as given one at a time by
+/// [`display_column`].
+pub fn display_columns(line: &[char], tabstop: u16) -> Vec<u16> {
+    let mut columns = Vec::with_capacity(line.len() + 1);
+    let mut display_column = 0;
+    for grapheme in graphemes(line) {
+        columns.push(display_column as u16);
+        display_column += grapheme_width(&line[grapheme.clone()], display_column, tabstop);
+        // The columns inside a grapheme are past its start, so after the whole grapheme
+        columns.extend(std::iter::repeat_n(
+            display_column as u16,
+            grapheme.len() - 1,
+        ));
+    }
+    columns.push(display_column as u16);
+    columns
+}
+
+/// Display column of the character at the given column.
+pub fn display_column(line: &[char], column: u16, tabstop: u16) -> u16 {
+    let mut display_column = 0;
+    for grapheme in graphemes(line) {
+        if grapheme.start >= column as usize {
+            break;
+        }
+        display_column += grapheme_width(&line[grapheme], display_column, tabstop);
+    }
+    display_column as u16
+}
+
+/// Column of the grapheme covering the given display column, the end of the line when past it.
+pub fn column_at(line: &[char], display_column: u16, tabstop: u16) -> u16 {
+    let mut end = 0;
+    for grapheme in graphemes(line) {
+        end += grapheme_width(&line[grapheme.clone()], end, tabstop);
+        if end > display_column as usize {
+            return grapheme.start as u16;
+        }
+    }
+    line.len() as u16
+}
+
+/// Text of a line as displayed, its tabs expanded to spaces.
+pub fn expand_tabs(line: &[char], tabstop: u16) -> String {
+    let mut text = String::new();
+    let mut display_column = 0;
+    for grapheme in graphemes(line) {
+        let grapheme = &line[grapheme];
+        let width = grapheme_width(grapheme, display_column, tabstop);
+        if grapheme == ['\t'] {
+            text.extend(std::iter::repeat_n(' ', width));
+        } else {
+            text.extend(grapheme);
+        }
+        display_column += width;
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_column_should_count_cells_of_graphemes() {
+        let cases = [
+            ("abc", 2, 2),
+            ("日本語", 2, 4),
+            ("e\u{301}a", 2, 1),
+            ("\tx", 1, 8),
+            ("ab\tx", 3, 8),
+            ("👍🏽x", 2, 2),
+        ];
+        for (line, column, expected) in cases {
+            let line: Vec<char> = line.chars().collect();
+            assert_eq!(display_column(&line, column, 8), expected, "{:?}", line);
+            assert_eq!(column_at(&line, expected, 8), column, "{:?}", line);
+            let columns: Vec<u16> = (0..=line.len() as u16)
+                .map(|column| display_column(&line, column, 8))
+                .collect();
+            assert_eq!(display_columns(&line, 8), columns, "{:?}", line);
+        }
+    }
+}
@@ -0,0 +1,387 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::str::FromStr;
+
+use ratatui::style::{Color, Modifier, Style};
+use serde::Deserialize;
+
+use crate::paths::config_dir;
+
+/// Name of the theme used at startup.
+pub const DEFAULT_THEME: &str = "default";
+/// Themes shipped with the editor, found when no theme file of the same name exists.
+const BUILTIN_THEMES: [(&str, &str); 2] = [
+    (DEFAULT_THEME, include_str!("../themes/default.toml")),
+    ("daylight", include_str!("../themes/daylight.toml")),
+];
+
+/// Named style of a part of the user interface, set by the theme.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Group {
+    /// Text of the views
+    Normal,
+    LineNr,
+    /// Number of the cursor line
+    CursorLineNr,
+    /// Characters which aren't part of the text, such as `showbreak`
+    NonText,
+    /// Glyphs of the invisible characters in `list` mode
+    Whitespace,
+    /// Background of the cursor line
+    CursorLine,
+    /// Columns set with `colorcolumn`
+    ColorColumn,
+    IndentGuide,
+    /// Lines between the views
+    WinSeparator,
+    StatusLine,
+    /// Mode segment of the statusline, by mode
+    ModeNormal,
+    ModeInsert,
+    ModeVisual,
+    TabLine,
+    /// Label of the current tab page
+    TabLineSel,
+    /// Headings, as on the start screen
+    Title,
+    /// Keys to type, as on the start screen
+    Special,
+    /// Visual selection
+    Visual,
+    /// Search matches
+    Search,
+    /// Bracket at the cursor and its match
+    MatchParen,
+    /// Lines of the minimap shown in the view
+    MinimapViewport,
+    /// Lines of the minimap with search matches
+    MinimapSearch,
+    /// Lines of the minimap with diagnostics
+    MinimapDiagnostic,
+    /// Content of popups
+    Pmenu,
+    /// Selected item of popups, such as the default choice of a confirm dialog
+    PmenuSel,
+    /// Borders of popups
+    FloatBorder,
+    /// Signs of the marks
+    MarkSign,
+    /// Diagnostics, by severity
+    DiagnosticError,
+    DiagnosticWarn,
+    DiagnosticInfo,
+    DiagnosticHint,
+    ErrorMsg,
+    WarningMsg,
+    /// Prompt asking to press Enter
+    MoreMsg,
+}
+
+impl Group {
+    pub fn from_name(name: &str) -> Option<Group> {
+        match name {
+            "Normal" => Some(Group::Normal),
+            "LineNr" => Some(Group::LineNr),
+            "CursorLineNr" => Some(Group::CursorLineNr),
+            "NonText" => Some(Group::NonText),
+            "Whitespace" => Some(Group::Whitespace),
+            "CursorLine" => Some(Group::CursorLine),
+            "ColorColumn" => Some(Group::ColorColumn),
+            "IndentGuide" => Some(Group::IndentGuide),
+            "WinSeparator" => Some(Group::WinSeparator),
+            "StatusLine" => Some(Group::StatusLine),
+            "ModeNormal" => Some(Group::ModeNormal),
+            "ModeInsert" => Some(Group::ModeInsert),
+            "ModeVisual" => Some(Group::ModeVisual),
+            "TabLine" => Some(Group::TabLine),
+            "TabLineSel" => Some(Group::TabLineSel),
+            "Title" => Some(Group::Title),
+            "Special" => Some(Group::Special),
+            "Visual" => Some(Group::Visual),
+            "Search" => Some(Group::Search),
+            "MatchParen" => Some(Group::MatchParen),
+            "MinimapViewport" => Some(Group::MinimapViewport),
+            "MinimapSearch" => Some(Group::MinimapSearch),
+            "MinimapDiagnostic" => Some(Group::MinimapDiagnostic),
+            "Pmenu" => Some(Group::Pmenu),
+            "PmenuSel" => Some(Group::PmenuSel),
+            "FloatBorder" => Some(Group::FloatBorder),
+            "MarkSign" => Some(Group::MarkSign),
+            "DiagnosticError" => Some(Group::DiagnosticError),
+            "DiagnosticWarn" => Some(Group::DiagnosticWarn),
+            "DiagnosticInfo" => Some(Group::DiagnosticInfo),
+            "DiagnosticHint" => Some(Group::DiagnosticHint),
+            "ErrorMsg" => Some(Group::ErrorMsg),
+            "WarningMsg" => Some(Group::WarningMsg),
+            "MoreMsg" => Some(Group::MoreMsg),
+            _ => None,
+        }
+    }
+}
+
+/// Highlight group as written in a theme file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct GroupSpec {
+    /// Colour name, `#rrggbb` or index in the 256 colours palette
+    fg: Option<String>,
+    bg: Option<String>,
+    #[serde(default)]
+    bold: bool,
+    #[serde(default)]
+    italic: bool,
+    #[serde(default)]
+    underline: bool,
+    #[serde(default)]
+    reversed: bool,
+}
+
+impl GroupSpec {
+    fn style(&self, colors: ColorSupport) -> Result<Style, String> {
+        let color = |color: &Option<String>| {
+            color
+                .as_deref()
+                .map(|color| {
+                    Color::from_str(color)
+                        .map(|color| colors.adapt(color))
+                        .map_err(|_| format!("Unknown colour: {}", color))
+                })
+                .transpose()
+        };
+        let mut style = Style {
+            fg: color(&self.fg)?,
+            bg: color(&self.bg)?,
+            ..Style::default()
+        };
+        for (enabled, modifier) in [
+            (self.bold, Modifier::BOLD),
+            (self.italic, Modifier::ITALIC),
+            (self.underline, Modifier::UNDERLINED),
+            (self.reversed, Modifier::REVERSED),
+        ] {
+            if enabled {
+                style = style.add_modifier(modifier);
+            }
+        }
+        Ok(style)
+    }
+}
+
+/// Styles of the highlight groups.
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub name: String,
+    styles: BTreeMap<Group, Style>,
+}
+
+impl Theme {
+    /// Loads a theme from `themes/<name>.toml` in the configuration directory, or among the
+    /// builtin ones, its colours downgraded to what the terminal supports. Groups missing from
+    /// the theme keep their default style.
+    pub fn load(name: &str, colors: ColorSupport) -> Result<Theme, String> {
+        let user_theme = config_dir()
+            .map(|directory| directory.join("themes").join(format!("{}.toml", name)))
+            .filter(|path| path.exists());
+        let content = match user_theme {
+            Some(path) => fs::read_to_string(&path).map_err(|e| e.to_string())?,
+            None => BUILTIN_THEMES
+                .iter()
+                .find(|(builtin, _)| *builtin == name)
+                .map(|(_, content)| content.to_string())
+                .ok_or(format!("Cannot find theme: {}", name))?,
+        };
+        let mut theme = Theme::parse(DEFAULT_THEME, BUILTIN_THEMES[0].1, colors)?;
+        let overrides = Theme::parse(name, &content, colors)?;
+        theme.name = overrides.name;
+        theme.styles.extend(overrides.styles);
+        Ok(theme)
+    }
+
+    fn parse(name: &str, content: &str, colors: ColorSupport) -> Result<Theme, String> {
+        let specs: BTreeMap<String, GroupSpec> =
+            toml::from_str(content).map_err(|e| format!("Invalid theme {}: {}", name, e))?;
+        let mut styles = BTreeMap::new();
+        for (group_name, spec) in specs {
+            let group = Group::from_name(&group_name)
+                .ok_or(format!("Unknown highlight group: {}", group_name))?;
+            styles.insert(group, spec.style(colors)?);
+        }
+        Ok(Theme {
+            name: name.to_string(),
+            styles,
+        })
+    }
+
+    pub fn style(&self, group: Group) -> Style {
+        self.styles.get(&group).copied().unwrap_or_default()
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::parse(DEFAULT_THEME, BUILTIN_THEMES[0].1, ColorSupport::TrueColor)
+            .expect("the default theme is valid")
+    }
+}
+
+/// Colours the terminal can show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorSupport {
+    TrueColor,
+    Ansi256,
+    Ansi16,
+}
+
+/// Colours of the 16 colours palette, as shown by xterm.
+const ANSI_COLORS: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+/// Levels of the components in the 6x6x6 colour cube of the 256 colours palette.
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+impl ColorSupport {
+    /// Guesses the colours of the terminal from `COLORTERM` and `TERM`.
+    pub fn detect() -> ColorSupport {
+        ColorSupport::from_env(
+            env::var("COLORTERM").ok().as_deref(),
+            env::var("TERM").ok().as_deref(),
+        )
+    }
+
+    fn from_env(colorterm: Option<&str>, term: Option<&str>) -> ColorSupport {
+        match (colorterm, term) {
+            (Some("truecolor" | "24bit"), _) => ColorSupport::TrueColor,
+            (_, Some(term)) if term.contains("256color") => ColorSupport::Ansi256,
+            _ => ColorSupport::Ansi16,
+        }
+    }
+
+    /// Closest colour the terminal can show.
+    pub fn adapt(self, color: Color) -> Color {
+        match (self, color) {
+            (ColorSupport::Ansi256, Color::Rgb(r, g, b)) => Color::Indexed(to_256((r, g, b))),
+            (ColorSupport::Ansi16, Color::Rgb(r, g, b)) => to_16((r, g, b)),
+            (ColorSupport::Ansi16, Color::Indexed(index)) => to_16(rgb_of_index(index)),
+            _ => color,
+        }
+    }
+}
+
+fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> u32 {
+    [(r1, r2), (g1, g2), (b1, b2)]
+        .iter()
+        .map(|(c1, c2)| (c1.abs_diff(*c2) as u32).pow(2))
+        .sum()
+}
+
+/// Closest colour of the cube or of the grey ramp of the 256 colours palette.
+fn to_256(rgb: (u8, u8, u8)) -> u8 {
+    let level = |component: u8| match component {
+        0..48 => 0,
+        48..115 => 1,
+        _ => (component - 35) / 40,
+    };
+    let (r, g, b) = (level(rgb.0), level(rgb.1), level(rgb.2));
+    let cube_index = 16 + 36 * r + 6 * g + b;
+    let average = ((rgb.0 as u16 + rgb.1 as u16 + rgb.2 as u16) / 3) as u8;
+    let grey_index = 232 + (average.saturating_sub(8) / 10).min(23);
+    if distance(rgb, rgb_of_index(grey_index)) < distance(rgb, rgb_of_index(cube_index)) {
+        grey_index
+    } else {
+        cube_index
+    }
+}
+
+/// Closest colour of the 16 colours palette.
+fn to_16(rgb: (u8, u8, u8)) -> Color {
+    ANSI_COLORS
+        .iter()
+        .min_by_key(|(_, ansi)| distance(rgb, *ansi))
+        .map_or(Color::Reset, |(color, _)| *color)
+}
+
+fn rgb_of_index(index: u8) -> (u8, u8, u8) {
+    match index {
+        0..16 => ANSI_COLORS[index as usize].1,
+        16..232 => {
+            let index = index - 16;
+            (
+                CUBE_LEVELS[(index / 36) as usize],
+                CUBE_LEVELS[(index / 6 % 6) as usize],
+                CUBE_LEVELS[(index % 6) as usize],
+            )
+        }
+        _ => {
+            let grey = 8 + 10 * (index - 232);
+            (grey, grey, grey)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colors_should_downgrade_to_the_terminal_palette() {
+        let cases = [
+            (
+                ColorSupport::TrueColor,
+                Color::Rgb(1, 2, 3),
+                Color::Rgb(1, 2, 3),
+            ),
+            (
+                ColorSupport::Ansi256,
+                Color::Rgb(255, 0, 0),
+                Color::Indexed(196),
+            ),
+            (
+                ColorSupport::Ansi256,
+                Color::Rgb(0x30, 0x30, 0x30),
+                Color::Indexed(236),
+            ),
+            (ColorSupport::Ansi256, Color::Cyan, Color::Cyan),
+            (
+                ColorSupport::Ansi16,
+                Color::Rgb(250, 10, 10),
+                Color::LightRed,
+            ),
+            (ColorSupport::Ansi16, Color::Indexed(236), Color::Black),
+            (ColorSupport::Ansi16, Color::Indexed(4), Color::Blue),
+        ];
+        for (colors, color, expected) in cases {
+            assert_eq!(colors.adapt(color), expected, "{:?} {:?}", colors, color);
+        }
+        assert_eq!(
+            ColorSupport::from_env(None, Some("xterm-256color")),
+            ColorSupport::Ansi256
+        );
+        assert_eq!(
+            ColorSupport::from_env(Some("truecolor"), Some("xterm")),
+            ColorSupport::TrueColor
+        );
+
+        // The builtin theme, which a user one of the same name would hide from `Theme::load`
+        let (name, content) = BUILTIN_THEMES[1];
+        let theme = Theme::parse(name, content, ColorSupport::Ansi16).unwrap();
+        assert_eq!(theme.style(Group::Normal).bg, Some(Color::White));
+        assert_eq!(theme.style(Group::ModeNormal).bg, Some(Color::LightBlue));
+        assert_eq!(theme.style(Group::DiagnosticError).fg, Some(Color::Red));
+    }
+}
@@ -1,15 +1,42 @@
+use std::ops::Range;
+
 use ratatui::{
     buffer::Buffer,
     layout::{Constraint, Layout, Rect},
-    style::{Color, Style, Stylize},
-    text::Span,
-    widgets::{Block, Paragraph, Widget},
+    style::Style,
+    text::{Line, Span},
+    widgets::{Block, Clear, Paragraph, Widget},
 };
+use unicode_width::UnicodeWidthStr;
 
-use crate::app::Editor;
+use crate::{
+    app::{APP_NAME, Editor, EditorMode},
+    brackets::matching_pair,
+    bufferline::{OVERFLOW_LEFT, OVERFLOW_RIGHT, label, layout, unique_names},
+    filesbuffers::{BufferId, FileBuffer},
+    listchars::ListChars,
+    messages::Level,
+    minimap::{LINES_PER_ROW, braille_row, first_line},
+    options::Options,
+    popup::PopupContent,
+    signs::{DIAGNOSTICS_NAMESPACE, SIGN_COLUMN_WIDTH},
+    startscreen::{StartScreen, StartTarget},
+    statusline::Segment,
+    text::{display_column, display_columns, expand_tabs, grapheme_width, graphemes},
+    theme::{Group, Theme},
+    view::{Split, View, Views},
+    wrap::display_lines,
+};
 
-pub const LINE_NUMBERS_WIDTH: u16 = 5;
+/// Minimal number of digits of the line numbers, so that the gutter seldom changes width.
+const MIN_NUMBER_DIGITS: u16 = 3;
+/// Glyph of the indent guides.
+const INDENT_GUIDE: &str = "│";
+/// Number of lines around the visible ones looked at for the indentation of blank lines.
+const GUIDES_MARGIN: usize = 50;
 pub const FOOTER_SIZE: u16 = 2;
+pub const TABLINE_SIZE: u16 = 1;
+pub const BUFFERLINE_SIZE: u16 = 1;
 
 impl Widget for &Editor {
     /// Renders the user interface widgets.
@@ -20,97 +47,1072 @@ impl Widget for &Editor {
     // - https://github.com/ratatui/ratatui/tree/master/examples
     fn render(self, area: Rect, buf: &mut Buffer) {
         let content_height = area.height.saturating_sub(FOOTER_SIZE);
-        let mut file_buffer = self.buffers.get(self.current_file_path.clone());
-        let file_buffer_content = file_buffer.file;
-        let lines: Vec<String> = file_buffer_content
+        if self.tabs.list.len() > 1 {
+            render_tabline(self, area, buf);
+        }
+        if let Some(bufferline_area) = bufferline_area(self, area) {
+            render_bufferline(self, bufferline_area, buf);
+        }
+
+        let content_area = content_area(self, area);
+        let views = &self.tabs.current().views;
+        if let Some(start_screen) = &self.start_screen {
+            render_start_screen(self, start_screen, content_area, buf);
+        } else {
+            let areas = views_areas(views, content_area);
+            for (view, view_area) in views.list.iter().zip(areas.iter()) {
+                render_view(self, view, *view_area, buf);
+                render_separator(
+                    self.theme.style(Group::WinSeparator),
+                    views.split,
+                    *view_area,
+                    content_area,
+                    buf,
+                );
+            }
+        }
+
+        if content_height > FOOTER_SIZE {
+            let statusline_area = Rect {
+                y: area.y + area.height - FOOTER_SIZE,
+                height: 1,
+                ..area
+            };
+            render_statusline(self, statusline_area, buf);
+        }
+        render_messages(self, area, buf);
+        render_popups(self, buf);
+    }
+}
+
+/// Draws the popups over everything else, from the bottom one to the topmost one.
+fn render_popups(editor: &Editor, buf: &mut Buffer) {
+    for popup in editor.popups.iter() {
+        let popup_area = editor.popup_area(popup);
+        let mut block = Block::bordered()
+            .title(popup.title.as_str())
+            .border_type(ratatui::widgets::BorderType::Rounded)
+            .style(editor.theme.style(Group::FloatBorder));
+        let height = popup_area.height.saturating_sub(2) as usize;
+        let lines_number = popup.lines_number();
+        let scroll = usize::min(popup.scroll, lines_number.saturating_sub(height));
+        if lines_number > height {
+            let position = format!(" {}/{} ", scroll + height, lines_number);
+            block = block.title_bottom(Line::from(position).right_aligned());
+        }
+        let lines = match &popup.content {
+            PopupContent::Command(command_input) => vec![Line::from(match command_input.prompt {
+                ':' => "> ".to_string() + &command_input.input_field,
+                prompt => format!("{} {}", prompt, command_input.input_field),
+            })],
+            PopupContent::Text(lines) => lines[scroll..].to_vec(),
+            PopupContent::Confirm(confirm) => {
+                let mut lines: Vec<Line> = confirm.lines().into_iter().map(Line::from).collect();
+                // The choice made with Enter stands out
+                let mut choices = Vec::new();
+                for (index, label) in confirm.choice_labels().into_iter().enumerate() {
+                    if index > 0 {
+                        choices.push(Span::raw(", "));
+                    }
+                    if index == confirm.default {
+                        choices.push(Span::styled(label, editor.theme.style(Group::PmenuSel)));
+                    } else {
+                        choices.push(Span::raw(label));
+                    }
+                }
+                if let Some(last) = lines.last_mut() {
+                    *last = Line::from(choices);
+                }
+                lines
+            }
+        };
+        Clear.render(popup_area, buf);
+        Paragraph::new(lines)
+            .block(block)
+            .style(editor.theme.style(Group::Pmenu))
+            .render(popup_area, buf);
+    }
+}
+
+fn message_style(theme: &Theme, level: Level) -> Style {
+    match level {
+        Level::Info => theme.style(Group::Normal),
+        Level::Warning => theme.style(Group::WarningMsg),
+        Level::Error => theme.style(Group::ErrorMsg),
+    }
+}
+
+/// Draws the messages on the last line, or above it with a prompt when they don't fit.
+fn render_messages(editor: &Editor, area: Rect, buf: &mut Buffer) {
+    let theme = &editor.theme;
+    let mut lines: Vec<Line> = editor
+        .messages
+        .shown()
+        .iter()
+        .flat_map(|message| {
+            let style = message_style(theme, message.level);
+            message
+                .text
+                .lines()
+                .map(move |line| Line::styled(line.to_string(), style))
+        })
+        .collect();
+    if editor.messages.prompt {
+        lines.push(Line::styled(
+            "Press ENTER or type command to continue",
+            theme.style(Group::MoreMsg),
+        ));
+    }
+    // The latest lines are kept when they don't all fit
+    let height = u16::min(lines.len() as u16, area.height);
+    let skipped = lines.len() - height as usize;
+    let messages_area = Rect {
+        y: area.y + area.height - height,
+        height,
+        ..area
+    };
+    Clear.render(messages_area, buf);
+    Paragraph::new(lines.split_off(skipped)).render(messages_area, buf);
+}
+
+/// Draws the segments of the statusline, dropping or truncating them when they don't fit.
+fn render_statusline(editor: &Editor, area: Rect, buf: &mut Buffer) {
+    buf.set_style(area, editor.theme.style(Group::StatusLine));
+    let statusline = &editor.options.statusline;
+    let spans = |segments: &[Segment]| -> Vec<Span> {
+        segments
+            .iter()
+            .filter_map(|segment| segment_span(editor, *segment))
+            .collect()
+    };
+    let left = join_spans(spans(&statusline.left));
+    let center = join_spans(spans(&statusline.center));
+    let mut right_spans = spans(&statusline.right);
+    let available = area.width as usize;
+    let mut right = join_spans(right_spans.clone());
+    while !right_spans.is_empty() && left.width() + 1 + right.width() > available {
+        right_spans.remove(0);
+        right = join_spans(right_spans.clone());
+    }
+
+    let right_x = area.right().saturating_sub(right.width() as u16);
+    buf.set_line(right_x, area.y, &right, right.width() as u16);
+    let left_width = right_x.saturating_sub(area.x + 1);
+    if left.width() > left_width as usize && left_width > 0 {
+        buf.set_line(area.x, area.y, &left, left_width - 1);
+        buf.set_string(area.x + left_width - 1, area.y, "…", Style::default());
+    } else {
+        buf.set_line(area.x, area.y, &left, left_width);
+    }
+    let center_x = area.x + area.width.saturating_sub(center.width() as u16) / 2;
+    if center_x > area.x + left.width() as u16 && center_x + (center.width() as u16) < right_x {
+        buf.set_line(center_x, area.y, &center, center.width() as u16);
+    }
+}
+
+/// Line of spans separated by spaces.
+fn join_spans(spans: Vec<Span>) -> Line {
+    let mut line = Line::default();
+    for (index, span) in spans.into_iter().enumerate() {
+        if index > 0 {
+            line.push_span(" ");
+        }
+        line.push_span(span);
+    }
+    line
+}
+
+/// Text of a statusline segment, if it has anything to show.
+fn segment_span(editor: &Editor, segment: Segment) -> Option<Span<'static>> {
+    let view = editor.tabs.current().views.current();
+    let file_buffer = editor.buffers.get(view.buffer_id);
+    let text = match segment {
+        Segment::Mode => {
+            let group = match editor.editor_mode {
+                EditorMode::Normal => Group::ModeNormal,
+                EditorMode::Insert => Group::ModeInsert,
+                EditorMode::Visual => Group::ModeVisual,
+            };
+            let mode = editor.editor_mode.to_string().to_uppercase();
+            return Some(Span::styled(
+                format!(" {} ", mode),
+                editor.theme.style(group),
+            ));
+        }
+        Segment::FileName => file_buffer.name(),
+        Segment::Modified if file_buffer.modified => "[+]".to_string(),
+        Segment::FileType if !file_buffer.options.filetype.is_empty() => {
+            file_buffer.options.filetype.clone()
+        }
+        Segment::Encoding => "utf-8".to_string(),
+        Segment::FileFormat => file_buffer.options.fileformat.clone(),
+        Segment::Position => format!(
+            "{}:{}",
+            view.current_line + 1,
+            view.display_column(file_buffer) + 1
+        ),
+        Segment::Percentage => format!(
+            "{}%",
+            (view.current_line as u32 + 1) * 100 / file_buffer.lines_number.max(1) as u32
+        ),
+        Segment::Selection if editor.editor_mode == EditorMode::Visual => {
+            let start = editor.selection_start?;
+            if start.line == view.current_line {
+                format!("{} chars", start.column.abs_diff(view.current_column) + 1)
+            } else {
+                format!("{} lines", start.line.abs_diff(view.current_line) + 1)
+            }
+        }
+        Segment::PendingKeys => {
+            let register = editor
+                .selected_register
+                .map(|name| format!("\"{}", name))
+                .unwrap_or_default();
+            let pending = format!("{}{}{}", editor.repetitions, register, editor.pending_keys);
+            if pending.is_empty() {
+                return None;
+            }
+            pending
+        }
+        Segment::Recording => format!("recording @{}", editor.recording?),
+        Segment::Diagnostics => {
+            let severities = [
+                ("E", Group::DiagnosticError),
+                ("W", Group::DiagnosticWarn),
+                ("I", Group::DiagnosticInfo),
+                ("H", Group::DiagnosticHint),
+            ];
+            let counts = severities.map(|(severity, _)| {
+                file_buffer
+                    .signs
+                    .iter()
+                    .filter(|sign| sign.namespace == DIAGNOSTICS_NAMESPACE && sign.text == severity)
+                    .count()
+            });
+            // Errors and warnings are always counted, the other severities once there are some
+            let text: Vec<String> = severities
+                .iter()
+                .zip(counts)
+                .filter(|((severity, _), count)| *count > 0 || ["E", "W"].contains(severity))
+                .map(|((severity, _), count)| format!("{}:{}", severity, count))
+                .collect();
+            // Styled after the most severe diagnostic
+            let style = severities
+                .iter()
+                .zip(counts)
+                .find(|(_, count)| *count > 0)
+                .map_or(Style::default(), |((_, group), _)| {
+                    editor.theme.style(*group)
+                });
+            return Some(Span::styled(text.join(" "), style));
+        }
+        _ => return None,
+    };
+    Some(Span::raw(text))
+}
+
+/// Area left to the views once the tabline and the footer are removed.
+pub fn content_area(editor: &Editor, area: Rect) -> Rect {
+    let tabline_height = tabline_height(editor, area);
+    let bufferline_height = bufferline_area(editor, area).map_or(0, |area| area.height);
+    Rect {
+        y: area.y + tabline_height + bufferline_height,
+        height: area
+            .height
+            .saturating_sub(FOOTER_SIZE)
+            .saturating_sub(tabline_height + bufferline_height),
+        ..area
+    }
+}
+
+fn tabline_height(editor: &Editor, area: Rect) -> u16 {
+    if editor.tabs.list.len() > 1 {
+        u16::min(TABLINE_SIZE, area.height)
+    } else {
+        0
+    }
+}
+
+/// Row of the bufferline, below the tabline, when the `bufferline` option is set.
+pub fn bufferline_area(editor: &Editor, area: Rect) -> Option<Rect> {
+    let tabline_height = tabline_height(editor, area);
+    let height = u16::min(BUFFERLINE_SIZE, area.height.saturating_sub(tabline_height));
+    (editor.options.bufferline && height > 0).then_some(Rect {
+        y: area.y + tabline_height,
+        height,
+        ..area
+    })
+}
+
+/// Draws the labels of the listed buffers, the current one highlighted, with markers on the ends
+/// when some of them are scrolled out.
+fn render_bufferline(editor: &Editor, area: Rect, buf: &mut Buffer) {
+    let style = editor.theme.style(Group::TabLine);
+    buf.set_style(area, style);
+    let current = editor.tabs.current().views.current().buffer_id;
+    let (listed, labels) = bufferline_labels(editor, area.width);
+    for (index, label, x) in &labels {
+        let label_style = if listed[*index] == current {
+            editor.theme.style(Group::TabLineSel)
+        } else {
+            style
+        };
+        let x = area.x + x;
+        buf.set_span(
+            x,
+            area.y,
+            &Span::styled(label, label_style),
+            area.right() - x,
+        );
+    }
+    if labels.first().is_some_and(|(index, _, _)| *index > 0) {
+        buf.set_string(area.x, area.y, OVERFLOW_LEFT, style);
+    }
+    if labels
+        .last()
+        .is_some_and(|(index, _, _)| *index + 1 < listed.len())
+    {
+        buf.set_string(area.right() - 1, area.y, OVERFLOW_RIGHT, style);
+    }
+}
+
+/// Listed buffers, and the index, label and offset of the ones shown in a bufferline of the
+/// given width.
+fn bufferline_labels(editor: &Editor, width: u16) -> (Vec<BufferId>, Vec<(usize, String, u16)>) {
+    let listed = editor.buffers.listed();
+    let file_buffers: Vec<&FileBuffer> = listed.iter().map(|id| editor.buffers.get(*id)).collect();
+    let labels: Vec<String> = unique_names(&file_buffers)
+        .iter()
+        .zip(&file_buffers)
+        .map(|(name, file_buffer)| label(name, file_buffer.modified))
+        .collect();
+    let widths: Vec<u16> = labels.iter().map(|label| label.width() as u16).collect();
+    let current_id = editor.tabs.current().views.current().buffer_id;
+    let current = listed.iter().position(|id| *id == current_id).unwrap_or(0);
+    let shown = layout(&widths, current, width)
+        .into_iter()
+        .map(|(index, x)| (index, labels[index].clone(), x))
+        .collect();
+    (listed, shown)
+}
+
+/// Buffer whose label is drawn at a column of the bufferline.
+pub fn buffer_at(editor: &Editor, area: Rect, column: u16) -> Option<BufferId> {
+    let (listed, labels) = bufferline_labels(editor, area.width);
+    labels.into_iter().find_map(|(index, label, x)| {
+        let start = area.x + x;
+        (start..start + label.width() as u16)
+            .contains(&column)
+            .then_some(listed[index])
+    })
+}
+
+/// Draws the labels of the tab pages, the current one highlighted.
+fn render_tabline(editor: &Editor, area: Rect, buf: &mut Buffer) {
+    let style = editor.theme.style(Group::TabLine);
+    let tabline_area = Rect {
+        height: u16::min(TABLINE_SIZE, area.height),
+        ..area
+    };
+    buf.set_style(tabline_area, style);
+    let mut x = tabline_area.x;
+    for (index, label) in tab_labels(editor).into_iter().enumerate() {
+        let label = Span::styled(
+            label,
+            if index == editor.tabs.current {
+                editor.theme.style(Group::TabLineSel)
+            } else {
+                style
+            },
+        );
+        let (next_x, _) = buf.set_span(x, tabline_area.y, &label, tabline_area.right() - x);
+        x = next_x;
+        if x >= tabline_area.right() {
+            break;
+        }
+    }
+}
+
+fn tab_labels(editor: &Editor) -> Vec<String> {
+    editor
+        .tabs
+        .list
+        .iter()
+        .enumerate()
+        .map(|(index, tab_page)| format!(" {}: {} ", index + 1, tab_page.label(&editor.buffers)))
+        .collect()
+}
+
+/// Tab page whose label is drawn at a column of the tabline.
+pub fn tab_at(editor: &Editor, area: Rect, column: u16) -> Option<usize> {
+    let mut x = area.x;
+    for (index, label) in tab_labels(editor).iter().enumerate() {
+        x += label.width() as u16;
+        if column < x {
+            return Some(index);
+        }
+    }
+    None
+}
+
+/// Draws the version, the recent files and sessions with their keys, and some key hints.
+fn render_start_screen(editor: &Editor, start_screen: &StartScreen, area: Rect, buf: &mut Buffer) {
+    let title_style = editor.theme.style(Group::Title);
+    let key_style = editor.theme.style(Group::Special);
+    let mut lines = vec![
+        Line::styled(
+            format!("{} v{}", APP_NAME, env!("CARGO_PKG_VERSION")),
+            title_style,
+        ),
+        Line::default(),
+    ];
+    for (title, is_session) in [("Recent files", false), ("Recent sessions", true)] {
+        let entries: Vec<Line> = start_screen
+            .entries
             .iter()
-            .map(|line| line.iter().map(|c| c.to_string()).collect())
+            .filter(|entry| matches!(entry.target, StartTarget::Session(_)) == is_session)
+            .map(|entry| {
+                Line::from(vec![
+                    Span::styled(format!("[{}] ", entry.key), key_style),
+                    Span::raw(entry.label.clone()),
+                ])
+            })
             .collect();
-        let numbers: Vec<String> = (1..=lines.len()).map(|n| format!("{}.", n)).collect();
-        let numbers_text = numbers.join("\n");
-        let text = lines.join("\n");
-
-        file_buffer.scroll_y = file_buffer
-            .current_line
-            .saturating_sub(area.height - FOOTER_SIZE - 1);
-
-        let scroll = (file_buffer.scroll_y, 0);
-
-        let line_numbers = Paragraph::new(numbers_text)
-            .block(Block::new())
-            .scroll(scroll)
-            .fg(Color::from_u32(0x00969696))
-            .bg(Color::Black);
-        let line_numbers_area = Rect {
-            x: area.x,
-            y: area.y,
-            width: LINE_NUMBERS_WIDTH,
-            height: area.height - FOOTER_SIZE,
+        if !entries.is_empty() {
+            lines.push(Line::styled(title, title_style));
+            lines.extend(entries);
+            lines.push(Line::default());
+        }
+    }
+    for (keys, hint) in [
+        ("i", "start writing"),
+        (":e <file>", "open a file"),
+        (":q", "quit"),
+    ] {
+        lines.push(Line::from(vec![
+            Span::styled(format!("{:<10}", keys), key_style),
+            Span::raw(hint),
+        ]));
+    }
+    let width = lines.iter().map(Line::width).max().unwrap_or_default() as u16;
+    let height = lines.len() as u16;
+    let start_area = Rect {
+        x: area.x + area.width.saturating_sub(width) / 2,
+        y: area.y + area.height.saturating_sub(height) / 2,
+        width: u16::min(width, area.width),
+        height: u16::min(height, area.height),
+    };
+    buf.set_style(area, editor.theme.style(Group::Normal));
+    Paragraph::new(lines).render(start_area, buf);
+}
+
+/// Shares the content area between the views, keeping one cell between them for a separator.
+pub fn views_areas(views: &Views, area: Rect) -> Vec<Rect> {
+    let constraints: Vec<Constraint> = match &views.sizes {
+        Some(sizes) if sizes.len() == views.list.len() => {
+            sizes.iter().map(|size| Constraint::Fill(*size)).collect()
+        }
+        _ => views.list.iter().map(|_| Constraint::Fill(1)).collect(),
+    };
+    let layout = match views.split {
+        Split::Horizontal => Layout::vertical(constraints),
+        Split::Vertical => Layout::horizontal(constraints),
+    };
+    layout.spacing(1).split(area).to_vec()
+}
+
+fn render_view(editor: &Editor, view: &View, area: Rect, buf: &mut Buffer) {
+    let file_buffer = editor.buffers.get(view.buffer_id);
+    let file_buffer_content = &file_buffer.file;
+    let line_numbers_area = Rect {
+        width: u16::min(gutter_width(file_buffer, &editor.options), area.width),
+        ..area
+    };
+    let number_style = editor.theme.style(Group::LineNr);
+    let sign_column = has_sign_column(file_buffer, &editor.options);
+    let show_numbers = editor.options.number || editor.options.relativenumber;
+    let gutter_line = |index: usize| {
+        let line = index as u16;
+        let mut spans = Vec::new();
+        if sign_column {
+            let sign = file_buffer.signs.get(line);
+            let text = sign.map_or("", |sign| sign.text.as_str());
+            let style = sign.map_or(Style::default(), |sign| sign.style);
+            let padding = (SIGN_COLUMN_WIDTH as usize).saturating_sub(text.width());
+            spans.push(Span::styled(text.to_string() + &" ".repeat(padding), style));
+        }
+        if !show_numbers {
+            return Line::from(spans);
+        }
+        // Both options together show the number of the cursor line, and the distances elsewhere
+        let label = match (editor.options.relativenumber, editor.options.number) {
+            (true, _) if line != view.current_line => line.abs_diff(view.current_line),
+            (true, false) => 0,
+            _ => line + 1,
         };
+        let style = if line == view.current_line {
+            editor.theme.style(Group::CursorLineNr)
+        } else {
+            number_style
+        };
+        spans.push(Span::styled(format!("{}.", label), style));
+        Line::from(spans)
+    };
+    let minimap_area = Rect {
+        x: area.right() - minimap_width(&editor.options, area.width),
+        width: minimap_width(&editor.options, area.width),
+        ..area
+    };
+    let text_width = (area.width - line_numbers_area.width).saturating_sub(minimap_area.width);
+    let tabstop = file_buffer.options.tabstop;
+    let text_spans = |line: &[char], range: Range<usize>| -> Vec<Span<'static>> {
+        if editor.options.list {
+            let style = editor.theme.style(Group::Whitespace);
+            list_spans(line, range, tabstop, &editor.options.listchars, style)
+        } else {
+            vec![Span::raw(expand_tabs(&line[range], tabstop))]
+        }
+    };
+    // Line shown on each row, and the column its display line starts at
+    let mut rows: Vec<(usize, usize)> = Vec::new();
 
-        line_numbers.render(line_numbers_area, buf);
-
-        let paragraph = Paragraph::new(text)
-            .block(Block::new())
-            .scroll(scroll)
-            .fg(Color::Cyan)
-            .bg(Color::Black);
-        let paragraph_area = Rect {
-            x: area.x + LINE_NUMBERS_WIDTH,
-            y: area.y,
-            width: area.width,
-            height: area.height - FOOTER_SIZE,
+    // Only the visible lines are built, the first one at the top
+    let (numbers, lines, scroll_x): (Vec<Line>, Vec<Line>, u16) = if editor.options.wrap {
+        let showbreak = &editor.options.showbreak;
+        let showbreak_width = editor.options.showbreak_width();
+        let mut numbers = Vec::new();
+        let mut lines = Vec::new();
+        for (index, line) in file_buffer_content
+            .iter()
+            .enumerate()
+            .skip(view.scroll_y as usize)
+        {
+            if lines.len() >= area.height as usize {
+                break;
+            }
+            for (range_index, range) in display_lines(line, text_width, showbreak_width, tabstop)
+                .into_iter()
+                .enumerate()
+                .take(area.height as usize - lines.len())
+            {
+                rows.push((index, range.start));
+                let mut spans = text_spans(line, range);
+                if range_index == 0 {
+                    numbers.push(gutter_line(index));
+                } else {
+                    numbers.push(Line::default());
+                    let showbreak =
+                        Span::styled(showbreak.clone(), editor.theme.style(Group::NonText));
+                    spans.insert(0, showbreak);
+                }
+                lines.push(Line::from(spans));
+            }
+        }
+        (numbers, lines, 0)
+    } else {
+        let visible_lines = file_buffer_content
+            .iter()
+            .enumerate()
+            .skip(view.scroll_y as usize)
+            .take(area.height as usize);
+        let numbers = visible_lines
+            .clone()
+            .map(|(index, _)| gutter_line(index))
+            .collect();
+        let lines = visible_lines
+            .map(|(index, line)| {
+                rows.push((index, 0));
+                Line::from(text_spans(line, 0..line.len()))
+            })
+            .collect();
+        (numbers, lines, view.scroll_x)
+    };
+
+    let line_numbers = Paragraph::new(numbers)
+        .block(Block::new())
+        .style(number_style);
+
+    line_numbers.render(line_numbers_area, buf);
+
+    let paragraph = Paragraph::new(lines)
+        .block(Block::new())
+        .scroll((0, scroll_x))
+        .style(editor.theme.style(Group::Normal));
+    let paragraph_area = Rect {
+        x: line_numbers_area.right(),
+        width: text_width,
+        ..area
+    };
+
+    paragraph.render(paragraph_area, buf);
+
+    // Layers drawn over the text
+    let is_current = std::ptr::eq(view, editor.tabs.current().views.current());
+    let selection = editor.selection().filter(|_| is_current);
+    let pattern: Vec<char> = editor.search_pattern.chars().collect();
+    let cell_x = |display_column: u16| {
+        display_column
+            .checked_sub(scroll_x)
+            .filter(|x| *x < paragraph_area.width)
+            .map(|x| paragraph_area.x + x)
+    };
+    let guides_lines = (view.scroll_y as usize).saturating_sub(GUIDES_MARGIN)
+        ..usize::min(
+            rows.last().map_or(0, |(index, _)| index + 1) + GUIDES_MARGIN,
+            file_buffer_content.len(),
+        );
+    for (row, (index, start)) in rows.iter().enumerate() {
+        let y = paragraph_area.y + row as u16;
+        if y >= paragraph_area.bottom() {
+            break;
+        }
+        if editor.options.cursorline && *index == view.current_line as usize {
+            let row_area = Rect {
+                y,
+                height: 1,
+                ..paragraph_area
+            };
+            buf.set_style(row_area, editor.theme.style(Group::CursorLine));
+        }
+        for column in &editor.options.colorcolumn {
+            if let Some(x) = cell_x(column - 1) {
+                buf[(x, y)].set_style(editor.theme.style(Group::ColorColumn));
+            }
+        }
+        // Past the end of the line, one more cell shows that its line break is selected
+        let text = &file_buffer_content[*index];
+        let row_end = rows
+            .get(row + 1)
+            .filter(|(next, _)| next == index)
+            .map_or(text.len() + 1, |(_, next_start)| *next_start);
+        // Display columns of the row, computed once for all its highlights
+        let columns = display_columns(&text[*start..usize::min(row_end, text.len())], tabstop);
+        let cell = |column: usize| {
+            let in_row = usize::min(column - start, columns.len() - 1);
+            let mut cell = columns[in_row];
+            if *start > 0 {
+                cell += editor.options.showbreak_width();
+            }
+            cell + (column - start - in_row) as u16
+        };
+        let mut highlight = |from: usize, to: usize, group: Group| {
+            let (from, to) = (usize::max(from, *start), usize::min(to, row_end));
+            if from < to {
+                for x in (cell(from)..cell(to)).filter_map(cell_x) {
+                    buf[(x, y)].set_style(editor.theme.style(group));
+                }
+            }
         };
+        if !pattern.is_empty() {
+            // Only the matches overlapping the row, which may be a small part of a long line
+            let from = start.saturating_sub(pattern.len() - 1);
+            let to = usize::min(row_end + pattern.len() - 1, text.len());
+            for (offset, _) in text[from..usize::max(from, to)]
+                .windows(pattern.len())
+                .enumerate()
+                .filter(|(_, window)| *window == pattern)
+            {
+                highlight(from + offset, from + offset + pattern.len(), Group::Search);
+            }
+        }
+        if let Some(selection) = &selection
+            && (selection.start.0..=selection.end.0).contains(&(*index as u16))
+        {
+            let from = match selection.start {
+                (line, column) if line as usize == *index && !selection.linewise => column as usize,
+                _ => 0,
+            };
+            let to = match selection.end {
+                (line, column) if line as usize == *index && !selection.linewise => column as usize,
+                _ => text.len() + 1,
+            };
+            highlight(from, to, Group::Visual);
+        }
+        if editor.options.indentguides && *start == 0 {
+            let indent = guides_indent(file_buffer_content, *index, tabstop, &guides_lines);
+            for level in (0..indent).step_by(usize::max(tabstop as usize, 1)) {
+                if let Some(x) = cell_x(level)
+                    && buf[(x, y)].symbol() == " "
+                {
+                    buf[(x, y)]
+                        .set_symbol(INDENT_GUIDE)
+                        .set_style(editor.theme.style(Group::IndentGuide));
+                }
+            }
+        }
+    }
 
-        paragraph.render(paragraph_area, buf);
+    let visible_lines = view.scroll_y as usize..rows.last().map_or(0, |(index, _)| index + 1);
+    if !minimap_area.is_empty() {
+        render_minimap(
+            editor,
+            file_buffer,
+            visible_lines.clone(),
+            minimap_area,
+            buf,
+        );
+    }
 
-        if content_height > FOOTER_SIZE {
-            let footer_y = area.y + area.height - FOOTER_SIZE; // last line inside border
-            let footer_text = Span::raw(format!(
-                "Mode: {}    Current line : {}/{}",
-                self.editor_mode,
-                file_buffer.current_line + 1,
-                file_buffer.lines_number,
-            ));
-            buf.set_span(
-                area.x + 1,
-                footer_y,
-                &footer_text,
-                footer_text.width() as u16,
-            );
+    // The bracket at the cursor of the current view and its match, when both are visible
+    let cursor = (view.current_line, view.current_column);
+    let filetype = &file_buffer.options.filetype;
+    let pair = is_current
+        .then(|| matching_pair(file_buffer_content, cursor, filetype, visible_lines))
+        .flatten();
+    for (line, column) in pair.into_iter().flatten() {
+        let (line, column) = (line as usize, column as usize);
+        let Some((row, (_, start))) = rows
+            .iter()
+            .enumerate()
+            .rfind(|(_, (index, start))| *index == line && *start <= column)
+        else {
+            continue;
+        };
+        let text = &file_buffer_content[line][*start..];
+        let mut bracket_column = display_column(text, (column - start) as u16, tabstop);
+        if *start > 0 {
+            bracket_column += editor.options.showbreak_width();
+        }
+        if let Some(x) = cell_x(bracket_column) {
+            let y = paragraph_area.y + row as u16;
+            buf[(x, y)].set_style(editor.theme.style(Group::MatchParen));
         }
+    }
+}
 
-        if self.command_popup.running {
-            let block = Block::bordered()
-                .title("Command pane")
-                .border_type(ratatui::widgets::BorderType::Rounded)
-                .style(Style::default().bg(Color::Black).fg(Color::White));
+/// Draws the overview of a buffer, highlighting the visible lines and the ones with search
+/// matches or diagnostics.
+fn render_minimap(
+    editor: &Editor,
+    file_buffer: &FileBuffer,
+    visible_lines: Range<usize>,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let lines = &file_buffer.file;
+    let first = first_line(
+        lines.len(),
+        visible_lines.start,
+        visible_lines.len(),
+        area.height as usize,
+    );
+    let pattern: Vec<char> = editor.search_pattern.chars().collect();
+    buf.set_style(area, editor.theme.style(Group::Normal));
+    for row in 0..area.height {
+        let start = first + row as usize * LINES_PER_ROW;
+        if start >= lines.len() {
+            break;
+        }
+        let rows_lines = start..usize::min(start + LINES_PER_ROW, lines.len());
+        let mut style = editor.theme.style(Group::Normal);
+        if rows_lines.start < visible_lines.end && visible_lines.start < rows_lines.end {
+            style = style.patch(editor.theme.style(Group::MinimapViewport));
+        }
+        let has_match = |line: &Vec<char>| {
+            !pattern.is_empty() && line.windows(pattern.len()).any(|window| window == pattern)
+        };
+        if lines[rows_lines.clone()].iter().any(has_match) {
+            style = style.patch(editor.theme.style(Group::MinimapSearch));
+        }
+        if file_buffer.signs.iter().any(|sign| {
+            sign.namespace == DIAGNOSTICS_NAMESPACE && rows_lines.contains(&(sign.line as usize))
+        }) {
+            style = style.patch(editor.theme.style(Group::MinimapDiagnostic));
+        }
+        let text = braille_row(
+            &lines[rows_lines],
+            area.width as usize,
+            file_buffer.options.tabstop,
+        );
+        buf.set_string(area.x, area.y + row, text, style);
+    }
+}
 
-            let command_paragraph =
-                Paragraph::new("> ".to_string() + &self.command_popup.input_field.clone())
-                    .block(block)
-                    .fg(Color::Cyan)
-                    .bg(Color::Black);
+/// Number of cells of the minimap of a view as wide as given, none when it is hidden or the
+/// view is too narrow.
+pub fn minimap_width(options: &Options, width: u16) -> u16 {
+    if options.minimap && width >= options.minimapwidth.saturating_mul(4) {
+        options.minimapwidth
+    } else {
+        0
+    }
+}
 
-            command_paragraph.render(centered_rect(60, 20, area), buf);
+/// Text of a part of a line in `list` mode, its invisible characters shown with the
+/// `listchars` glyphs.
+fn list_spans(
+    line: &[char],
+    range: Range<usize>,
+    tabstop: u16,
+    list_chars: &ListChars,
+    style: Style,
+) -> Vec<Span<'static>> {
+    let trail_start = line
+        .iter()
+        .rposition(|c| !matches!(c, ' ' | '\t'))
+        .map_or(0, |last| last + 1);
+    let mut spans = Vec::new();
+    let mut text = String::new();
+    let mut display_column = 0;
+    for grapheme in graphemes(&line[range.clone()]) {
+        let column = range.start + grapheme.start;
+        let grapheme = &line[column..range.start + grapheme.end];
+        let width = grapheme_width(grapheme, display_column, tabstop);
+        display_column += width;
+        let glyphs = match grapheme {
+            ['\t'] => list_chars.tab_glyphs(width),
+            [' '] if column >= trail_start => list_chars.trail.map(String::from),
+            ['\u{a0}'] => list_chars.nbsp.map(String::from),
+            _ => None,
+        };
+        match glyphs {
+            Some(glyphs) => {
+                if !text.is_empty() {
+                    spans.push(Span::raw(std::mem::take(&mut text)));
+                }
+                spans.push(Span::styled(glyphs, style));
+            }
+            None if grapheme == ['\t'] => text.extend(std::iter::repeat_n(' ', width)),
+            None => text.extend(grapheme),
         }
     }
+    if !text.is_empty() {
+        spans.push(Span::raw(text));
+    }
+    if range.end == line.len()
+        && let Some(eol) = list_chars.eol
+    {
+        spans.push(Span::styled(eol.to_string(), style));
+    }
+    spans
 }
 
-fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
-    let vertical_chunks = Layout::vertical([
-        Constraint::Percentage((100 - percent_y) / 2),
-        Constraint::Percentage(percent_y),
-        Constraint::Percentage((100 - percent_y) / 2),
-    ])
-    .split(r);
+/// Display width of the indentation of a line, none when it is blank.
+fn indent_width(line: &[char], tabstop: u16) -> Option<u16> {
+    let first = line.iter().position(|c| !matches!(c, ' ' | '\t'))?;
+    Some(display_column(line, first as u16, tabstop))
+}
 
-    let horizontal_chunks = Layout::horizontal([
-        Constraint::Percentage((100 - percent_x) / 2),
-        Constraint::Percentage(percent_x),
-        Constraint::Percentage((100 - percent_x) / 2),
-    ])
-    .split(vertical_chunks[1]);
+/// Indentation the guides of a line are drawn for. Blank lines take the smallest indentation
+/// of the lines around them, looked for among the given ones only, so that the guides run
+/// through them.
+fn guides_indent(lines: &[Vec<char>], index: usize, tabstop: u16, around: &Range<usize>) -> u16 {
+    indent_width(&lines[index], tabstop).unwrap_or_else(|| {
+        let previous = lines[around.start.min(index)..index]
+            .iter()
+            .rev()
+            .find_map(|line| indent_width(line, tabstop));
+        let next = lines[index + 1..around.end.max(index + 1)]
+            .iter()
+            .find_map(|line| indent_width(line, tabstop));
+        u16::min(previous.unwrap_or(0), next.unwrap_or(0))
+    })
+}
+
+/// Width of the gutter on the left of a view: the sign column if shown, then the line numbers
+/// with enough digits for the last line, a dot and a space, if shown.
+pub fn gutter_width(file_buffer: &FileBuffer, options: &Options) -> u16 {
+    let sign_column_width = if has_sign_column(file_buffer, options) {
+        SIGN_COLUMN_WIDTH
+    } else {
+        0
+    };
+    if !options.number && !options.relativenumber {
+        return sign_column_width;
+    }
+    let digits = file_buffer
+        .lines_number
+        .checked_ilog10()
+        .unwrap_or_default() as u16
+        + 1;
+    sign_column_width + u16::max(digits, MIN_NUMBER_DIGITS) + 2
+}
+
+fn has_sign_column(file_buffer: &FileBuffer, options: &Options) -> bool {
+    match options.signcolumn.as_str() {
+        "yes" => true,
+        "no" => false,
+        _ => !file_buffer.signs.is_empty(),
+    }
+}
+
+/// Draws the separator following a view, in the spacing left by [`views_areas`].
+fn render_separator(style: Style, split: Split, area: Rect, bounds: Rect, buf: &mut Buffer) {
+    match split {
+        Split::Horizontal if area.bottom() < bounds.bottom() => {
+            for x in area.left()..area.right() {
+                buf[(x, area.bottom())].set_symbol("─").set_style(style);
+            }
+        }
+        Split::Vertical if area.right() < bounds.right() => {
+            for y in area.top()..area.bottom() {
+                buf[(area.right(), y)].set_symbol("│").set_style(style);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{signs::Sign, tabpage::TabPage, view::View};
+    use ratatui::style::Modifier;
+
+    /// Text of the spans, the styled ones in brackets.
+    fn marked(spans: &[Span], style: Style) -> String {
+        spans
+            .iter()
+            .map(|span| match span.style == style {
+                true => format!("[{}]", span.content),
+                false => span.content.to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn segment_span_should_show_the_recording_and_diagnostics() {
+        let mut editor = Editor::with_lines(&["text", "more"]);
+        let text = |editor: &Editor, segment| {
+            segment_span(editor, segment).map(|span| span.content.to_string())
+        };
+        assert_eq!(text(&editor, Segment::Recording), None);
+        assert_eq!(text(&editor, Segment::Diagnostics).unwrap(), "E:0 W:0");
+
+        editor.recording = Some('q');
+        for (line, severity) in [(0, "W"), (1, "H"), (1, "W")] {
+            editor.buffers.get_mut(0).signs.place(Sign {
+                namespace: DIAGNOSTICS_NAMESPACE,
+                line,
+                text: severity.to_string(),
+                style: Style::default(),
+                priority: 20,
+            });
+        }
+        assert_eq!(text(&editor, Segment::Recording).unwrap(), "recording @q");
+        let span = segment_span(&editor, Segment::Diagnostics).unwrap();
+        assert_eq!(span.content, "E:0 W:2 H:1");
+        assert_eq!(span.style, editor.theme.style(Group::DiagnosticWarn));
+    }
+
+    #[test]
+    fn list_spans_should_show_invisible_characters() {
+        let style = Style::new().add_modifier(Modifier::BOLD);
+        let list_chars = ListChars::parse("tab:>-,trail:~,nbsp:+,eol:$").unwrap();
+        let lines = ["ab\tc  ", "a\u{a0}b", "x \ty", "\t\t", "abcdef"];
+        let ranges = [0..6, 0..3, 0..4, 0..2, 0..3];
+        let expect = [
+            "ab[>-]c[~][~][$]",
+            "a[+]b[$]",
+            "x [>-]y[$]",
+            "[>---][>---][$]",
+            "abc",
+        ];
+        for ((line, range), expect) in lines.into_iter().zip(ranges).zip(expect) {
+            let line: Vec<char> = line.chars().collect();
+            let spans = list_spans(&line, range, 4, &list_chars, style);
+            assert_eq!(marked(&spans, style), expect, "{:?}", line);
+        }
+
+        // Only the display line ending the line shows `eol`, and tabs start over on each one
+        let line: Vec<char> = "abc\tdef".chars().collect();
+        let list_chars = ListChars::parse("tab:<->,eol:$").unwrap();
+        let spans = list_spans(&line, 0..4, 4, &list_chars, style);
+        assert_eq!(marked(&spans, style), "abc[>]");
+        let spans = list_spans(&line, 3..7, 4, &list_chars, style);
+        assert_eq!(marked(&spans, style), "[<-->]def[$]");
+    }
+
+    #[test]
+    fn guides_indent_should_run_through_blank_lines() {
+        let lines: Vec<Vec<char>> = ["    a", "", "\t\tb", "   ", "  c", ""]
+            .iter()
+            .map(|line| line.chars().collect())
+            .collect();
+        assert_eq!(indent_width(&lines[0], 4), Some(4));
+        assert_eq!(indent_width(&lines[2], 4), Some(8));
+        assert_eq!(indent_width(&lines[3], 4), None);
+        let indents: Vec<u16> = (0..lines.len())
+            .map(|index| guides_indent(&lines, index, 4, &(0..lines.len())))
+            .collect();
+        assert_eq!(indents, [4, 4, 8, 2, 2, 0]);
+        // Lines out of reach don't count
+        assert_eq!(guides_indent(&lines, 1, 4, &(1..2)), 0);
+        assert_eq!(guides_indent(&lines, 3, 4, &(1..4)), 0);
+    }
 
-    horizontal_chunks[1]
+    #[test]
+    fn render_view_should_highlight_the_cursor_line_and_columns() {
+        let mut editor = Editor::with_lines(&["text", "more"]);
+        editor.options.cursorline = true;
+        editor.options.colorcolumn = vec![3];
+        let area = Rect::new(0, 0, 40, 10);
+        let mut buf = Buffer::empty(area);
+        editor.render(area, &mut buf);
+
+        let x = (0..area.width)
+            .find(|x| buf[(*x, 0)].symbol() == "t")
+            .unwrap();
+        let background = |x, y| buf[(x, y)].bg;
+        let cursor_line = editor.theme.style(Group::CursorLine).bg.unwrap();
+        let color_column = editor.theme.style(Group::ColorColumn).bg.unwrap();
+        assert_eq!(background(x, 0), cursor_line);
+        assert_eq!(background(x + 3, 0), cursor_line);
+        assert_eq!(background(x + 2, 0), color_column);
+        assert_eq!(background(x + 2, 1), color_column);
+        assert_ne!(background(x, 1), cursor_line);
+    }
+
+    #[test]
+    fn render_view_should_number_the_lines_as_the_options_say() {
+        let lines: Vec<String> = (0..12).map(|line| line.to_string()).collect();
+        let lines: Vec<&str> = lines.iter().map(String::as_str).collect();
+        let mut editor = Editor::with_lines(&lines);
+        editor.tabs.current_mut().views.current_mut().current_line = 2;
+        let area = Rect::new(0, 0, 40, 10);
+        let file_buffer = editor.buffers.get(0);
+        assert_eq!(gutter_width(file_buffer, &editor.options), 5);
+
+        let inputs = [(true, false), (false, true), (true, true)];
+        let expect = [
+            ["1.", "2.", "3.", "4."],
+            ["2.", "1.", "0.", "1."],
+            ["2.", "1.", "3.", "1."],
+        ];
+        for ((number, relativenumber), expect) in inputs.into_iter().zip(expect) {
+            editor.options.number = number;
+            editor.options.relativenumber = relativenumber;
+            let mut buf = Buffer::empty(area);
+            editor.render(area, &mut buf);
+            let gutter = |y| -> String { (0..5).map(|x| buf[(x, y)].symbol()).collect() };
+            let numbers: Vec<String> = (0..4).map(|y| gutter(y).trim().to_string()).collect();
+            assert_eq!(numbers, expect, "{} {}", number, relativenumber);
+            let current_number = editor.theme.style(Group::CursorLineNr).fg;
+            assert_eq!(Some(buf[(0, 2)].fg), current_number);
+            assert_ne!(Some(buf[(0, 1)].fg), current_number);
+        }
+
+        editor.options.number = false;
+        editor.options.relativenumber = false;
+        let file_buffer = editor.buffers.get(0);
+        assert_eq!(gutter_width(file_buffer, &editor.options), 0);
+        editor.options.number = true;
+        let file_buffer = editor.buffers.get_mut(0);
+        file_buffer.file = vec![vec![]; 12345];
+        file_buffer.lines_number = 12345;
+        assert_eq!(gutter_width(editor.buffers.get(0), &editor.options), 7);
+    }
+
+    #[test]
+    fn tab_at_should_find_the_label_under_a_column() {
+        let mut editor = Editor::with_lines(&[]);
+        editor.tabs.open(TabPage::new(View::new(0)));
+        // Labels " 1: [Scratch] " and " 2: [Scratch] ", 14 cells each
+        let area = Rect::new(2, 0, 78, 1);
+        let columns = [2, 15, 16, 29, 30, 70];
+        let expect = [Some(0), Some(0), Some(1), Some(1), None, None];
+        for (column, expect) in columns.into_iter().zip(expect) {
+            assert_eq!(tab_at(&editor, area, column), expect, "{}", column);
+        }
+    }
 }
@@ -0,0 +1,494 @@
+use ratatui::layout::{Position, Rect};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    filesbuffers::{BufferId, Edit, FileBuffer, Move},
+    jumplist::{JumpList, Location},
+    options::Options,
+    text::{boundary, column_at, display_column, next_boundary, previous_boundary},
+    ui::{gutter_width, minimap_width},
+    wrap::{display_line_index, display_lines},
+};
+
+/// How the views of a layout are laid out.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Split {
+    /// Views stacked on top of each other
+    #[default]
+    Horizontal,
+    /// Views side by side
+    Vertical,
+}
+
+/// Row of its view where `zt`, `zz` and `zb` put the line of the cursor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScrollAnchor {
+    Top,
+    Center,
+    Bottom,
+}
+
+/// Window on a file buffer.
+///
+/// Several views can show the same buffer, each one with its own cursor and viewport.
+#[derive(Default, Debug, Clone)]
+pub struct View {
+    /// Displayed file buffer
+    pub buffer_id: BufferId,
+    /// Cursor line
+    pub current_line: u16,
+    /// Cursor column
+    pub current_column: u16,
+    /// Display column the cursor goes back to when moving vertically through shorter lines
+    pub desired_column: u16,
+    /// First displayed line
+    pub scroll_y: u16,
+    /// First displayed cell, when lines are not wrapped
+    pub scroll_x: u16,
+    /// Width of the text when last displayed, used to move through display lines
+    pub text_width: u16,
+    /// Positions the cursor jumped from
+    pub jumps: JumpList,
+    /// Last position in the previously displayed buffer
+    pub alternate: Option<Location>,
+    /// Position before the latest jump
+    pub context: Option<Location>,
+}
+
+/// Views layout.
+#[derive(Default, Debug, Clone)]
+pub struct Views {
+    pub list: Vec<View>,
+    /// Index of the focused view
+    pub current: usize,
+    pub split: Split,
+    /// Cells given to each view once a separator was dragged, shared equally when none
+    pub sizes: Option<Vec<u16>>,
+}
+
+impl View {
+    pub fn new(buffer_id: BufferId) -> Self {
+        View {
+            buffer_id,
+            ..Default::default()
+        }
+    }
+
+    /// Displays another buffer, from its start. The current buffer becomes the alternate one.
+    pub fn show(&mut self, buffer_id: BufferId) {
+        if buffer_id == self.buffer_id {
+            return;
+        }
+        self.alternate = Some(self.location());
+        self.buffer_id = buffer_id;
+        self.current_line = 0;
+        self.current_column = 0;
+        self.desired_column = 0;
+        self.scroll_y = 0;
+        self.scroll_x = 0;
+    }
+
+    pub fn location(&self) -> Location {
+        Location {
+            buffer_id: self.buffer_id,
+            line: self.current_line,
+            column: self.current_column,
+        }
+    }
+
+    /// Moves the cursor to the location, switching buffer if needed.
+    pub fn go_to(&mut self, location: Location, file_buffer: &FileBuffer) {
+        self.show(location.buffer_id);
+        self.current_line = location.line;
+        self.current_column = location.column;
+        self.clamp(file_buffer);
+        self.remember_column(file_buffer);
+    }
+
+    /// Terminal position of the cursor when the view is rendered in `area`.
+    pub fn to_cursor_position(
+        &self,
+        area: Rect,
+        file_buffer: &FileBuffer,
+        options: &Options,
+    ) -> Position {
+        let (row, column) = if options.wrap {
+            self.wrapped_cursor(file_buffer, options)
+        } else {
+            (
+                self.current_line.saturating_sub(self.scroll_y),
+                self.display_column(file_buffer)
+                    .saturating_sub(self.scroll_x),
+            )
+        };
+        Position {
+            x: area.x + gutter_width(file_buffer, options) + column,
+            y: area.y + row,
+        }
+    }
+
+    /// Scrolls the viewport just enough for the cursor to be visible in `area`.
+    pub fn scroll_to_cursor(&mut self, area: Rect, file_buffer: &FileBuffer, options: &Options) {
+        let height = area.height;
+        self.text_width = area
+            .width
+            .saturating_sub(gutter_width(file_buffer, options))
+            .saturating_sub(minimap_width(options, area.width));
+        if self.current_line < self.scroll_y {
+            self.scroll_y = self.current_line;
+        } else if height > 0 && self.current_line >= self.scroll_y + height {
+            self.scroll_y = self.current_line + 1 - height;
+        }
+        if options.wrap {
+            self.scroll_x = 0;
+            while self.scroll_y < self.current_line
+                && self.wrapped_cursor(file_buffer, options).0 >= height
+            {
+                self.scroll_y += 1;
+            }
+        } else {
+            let width = self.text_width;
+            let offset = u16::min(options.sidescrolloff, width.saturating_sub(1) / 2);
+            let column = self.display_column(file_buffer);
+            if column < self.scroll_x + offset {
+                self.scroll_x = column.saturating_sub(offset);
+            } else if width > 0 && column + offset >= self.scroll_x + width {
+                self.scroll_x = column + offset + 1 - width;
+            }
+        }
+    }
+
+    /// Display row of the cursor below the first displayed line, and its display column, when
+    /// lines are wrapped.
+    fn wrapped_cursor(&self, file_buffer: &FileBuffer, options: &Options) -> (u16, u16) {
+        let showbreak_width = options.showbreak_width();
+        let tabstop = file_buffer.options.tabstop;
+        let line_ranges = |line: u16| {
+            display_lines(
+                file_buffer.line(line),
+                self.text_width,
+                showbreak_width,
+                tabstop,
+            )
+        };
+        let rows: usize = (self.scroll_y..self.current_line)
+            .map(|line| line_ranges(line).len())
+            .sum();
+        let ranges = line_ranges(self.current_line);
+        let index = display_line_index(&ranges, self.current_column);
+        let start = ranges[index].start;
+        let mut column = display_column(
+            &file_buffer.line(self.current_line)[start..],
+            self.current_column - start as u16,
+            tabstop,
+        );
+        if index > 0 {
+            column += showbreak_width;
+        }
+        ((rows + index) as u16, column)
+    }
+
+    /// Line and column of the text shown at a cell of the text area, the nearest one when the
+    /// cell is past the end of a line or of the buffer.
+    pub fn position_at(
+        &self,
+        file_buffer: &FileBuffer,
+        options: &Options,
+        x: u16,
+        y: u16,
+    ) -> (u16, u16) {
+        let tabstop = file_buffer.options.tabstop;
+        let last_line = file_buffer.lines_number.saturating_sub(1);
+        if !options.wrap {
+            let line = u16::min(self.scroll_y + y, last_line);
+            let column = column_at(file_buffer.line(line), self.scroll_x + x, tabstop);
+            return (line, column);
+        }
+        let showbreak_width = options.showbreak_width();
+        let mut row = 0;
+        for line in self.scroll_y..=last_line {
+            let text = file_buffer.line(line);
+            let ranges = display_lines(text, self.text_width, showbreak_width, tabstop);
+            for (index, range) in ranges.iter().enumerate() {
+                if row < y {
+                    row += 1;
+                    continue;
+                }
+                let x = if index > 0 {
+                    x.saturating_sub(showbreak_width)
+                } else {
+                    x
+                };
+                let column = range.start as u16 + column_at(&text[range.clone()], x, tabstop);
+                // Past the end of a display line continued below, the cursor stays on it
+                if index + 1 < ranges.len() && column as usize >= range.end {
+                    return (line, previous_boundary(text, range.end as u16));
+                }
+                return (line, column);
+            }
+        }
+        (last_line, file_buffer.line_len(last_line))
+    }
+
+    /// Moves the cursor to the next or previous display line, which is the next or previous
+    /// line unless lines are wrapped.
+    pub fn move_display_line(&mut self, file_buffer: &FileBuffer, options: &Options, down: bool) {
+        if !options.wrap || self.text_width == 0 {
+            self.move_cursor(file_buffer, if down { Move::Down } else { Move::Up });
+            return;
+        }
+        let showbreak_width = options.showbreak_width();
+        let tabstop = file_buffer.options.tabstop;
+        let line_ranges = |line: u16| {
+            display_lines(
+                file_buffer.line(line),
+                self.text_width,
+                showbreak_width,
+                tabstop,
+            )
+        };
+        let ranges = line_ranges(self.current_line);
+        let index = display_line_index(&ranges, self.current_column);
+        let start = ranges[index].start;
+        let mut offset = display_column(
+            &file_buffer.line(self.current_line)[start..],
+            self.current_column - start as u16,
+            tabstop,
+        );
+        if index > 0 {
+            offset += showbreak_width;
+        }
+        let (line, index) = match down {
+            true if index + 1 < ranges.len() => (self.current_line, index + 1),
+            true if self.current_line + 1 < file_buffer.lines_number => (self.current_line + 1, 0),
+            false if index > 0 => (self.current_line, index - 1),
+            false if self.current_line > 0 => {
+                let line = self.current_line - 1;
+                (line, line_ranges(line).len() - 1)
+            }
+            _ => return,
+        };
+        let ranges = line_ranges(line);
+        let range = ranges[index].clone();
+        if index > 0 {
+            offset = offset.saturating_sub(showbreak_width);
+        }
+        let text = file_buffer.line(line);
+        let mut column = range.start as u16 + column_at(&text[range.clone()], offset, tabstop);
+        if index + 1 < ranges.len() && column as usize >= range.end {
+            // The cursor stays on the display line rather than going to the next one
+            column = previous_boundary(text, range.end as u16);
+        }
+        self.current_line = line;
+        self.current_column = column;
+        self.remember_column(file_buffer);
+    }
+
+    /// Keeps the cursor and the remembered positions following the text after an edit of the
+    /// given buffer.
+    pub fn apply_edit(&mut self, buffer_id: BufferId, edit: Edit, file_buffer: &FileBuffer) {
+        self.jumps.apply_edit(buffer_id, edit);
+        for location in [self.alternate.as_mut(), self.context.as_mut()]
+            .into_iter()
+            .flatten()
+        {
+            location.apply_edit(buffer_id, edit);
+        }
+        if self.buffer_id == buffer_id {
+            edit.shift(&mut self.current_line, &mut self.current_column);
+            self.clamp(file_buffer);
+        }
+    }
+
+    /// Keeps the cursor inside the buffer, at the start of a grapheme.
+    pub fn clamp(&mut self, file_buffer: &FileBuffer) {
+        let last_line = file_buffer.file.len().saturating_sub(1) as u16;
+        self.current_line = u16::min(self.current_line, last_line);
+        self.current_column = boundary(file_buffer.line(self.current_line), self.current_column);
+    }
+
+    /// Display column of the cursor.
+    pub fn display_column(&self, file_buffer: &FileBuffer) -> u16 {
+        display_column(
+            file_buffer.line(self.current_line),
+            self.current_column,
+            file_buffer.options.tabstop,
+        )
+    }
+
+    /// Remembers the display column of the cursor, for moves through shorter lines.
+    pub fn remember_column(&mut self, file_buffer: &FileBuffer) {
+        self.desired_column = self.display_column(file_buffer);
+    }
+
+    pub fn move_cursor(&mut self, file_buffer: &FileBuffer, move_option: Move) {
+        let line = file_buffer.line(self.current_line);
+        match move_option {
+            Move::Left => {
+                self.current_column = previous_boundary(line, self.current_column);
+                self.remember_column(file_buffer);
+            }
+            Move::Up => {
+                if self.current_line > 0 {
+                    self.current_line -= 1
+                }
+                self.restore_desired_column(file_buffer);
+            }
+            Move::Right => {
+                self.current_column = next_boundary(line, self.current_column);
+                self.remember_column(file_buffer);
+            }
+            Move::Down => {
+                if self.current_line + 1 < file_buffer.lines_number {
+                    self.current_line += 1
+                }
+                self.restore_desired_column(file_buffer);
+            }
+        };
+    }
+
+    pub fn restore_desired_column(&mut self, file_buffer: &FileBuffer) {
+        self.current_column = column_at(
+            file_buffer.line(self.current_line),
+            self.desired_column,
+            file_buffer.options.tabstop,
+        );
+    }
+
+    pub fn move_to_next_word(&mut self, file_buffer: &FileBuffer) {
+        let line = file_buffer.line(self.current_line);
+        let columns_number = line.len() as u16;
+        let mut index = self.current_column;
+        let mut is_parsing_word = false;
+        while index < columns_number {
+            let Some(c) = line.get(index as usize) else {
+                return;
+            };
+            if is_parsing_word && *c == ' ' {
+                break;
+            }
+            if *c != ' ' {
+                is_parsing_word = true;
+            }
+            index += 1;
+        }
+        self.current_column = index;
+        self.remember_column(file_buffer);
+    }
+
+    pub fn move_to_previous_word(&mut self, file_buffer: &FileBuffer) {
+        if self.current_column < 1 {
+            return;
+        };
+        let line = file_buffer.line(self.current_line);
+        let mut index = self.current_column - 1;
+        let mut is_parsing_word = false;
+        while index > 0 {
+            let Some(c) = line.get(index as usize) else {
+                return;
+            };
+            if is_parsing_word && *c == ' ' {
+                break;
+            }
+            if *c != ' ' {
+                is_parsing_word = true;
+            }
+            index -= 1;
+        }
+        self.current_column = index;
+        self.remember_column(file_buffer);
+    }
+}
+
+impl Views {
+    pub fn new(view: View) -> Self {
+        Views {
+            list: vec![view],
+            current: 0,
+            split: Split::default(),
+            sizes: None,
+        }
+    }
+
+    pub fn current(&self) -> &View {
+        &self.list[self.current]
+    }
+
+    pub fn current_mut(&mut self) -> &mut View {
+        &mut self.list[self.current]
+    }
+
+    /// Opens a copy of the focused view next to it and focuses it.
+    pub fn split(&mut self, split: Split) {
+        let view = self.current().clone();
+        self.split = split;
+        self.current += 1;
+        self.list.insert(self.current, view);
+        self.sizes = None;
+    }
+
+    /// Closes the focused view. The last view can't be closed.
+    pub fn close(&mut self) -> bool {
+        if self.list.len() < 2 {
+            return false;
+        }
+        self.list.remove(self.current);
+        self.current = usize::min(self.current, self.list.len() - 1);
+        self.sizes = None;
+        true
+    }
+
+    pub fn focus_next(&mut self) {
+        self.current = (self.current + 1) % self.list.len();
+    }
+
+    pub fn focus_previous(&mut self) {
+        self.current = (self.current + self.list.len() - 1) % self.list.len();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filesbuffers::BufferKind;
+
+    #[test]
+    fn position_at_should_find_the_character_under_a_cell() {
+        let mut file_buffer = FileBuffer::new(
+            BufferKind::Scratch,
+            None,
+            ["\tab", "hello world", "x"]
+                .iter()
+                .map(|line| line.chars().collect())
+                .collect(),
+        );
+        file_buffer.options.tabstop = 4;
+        let mut view = View {
+            text_width: 8,
+            ..View::new(0)
+        };
+        let mut options = Options::default();
+
+        let cells = [(0, 0), (3, 0), (4, 0), (20, 0), (0, 10)];
+        let expect = [(0, 0), (0, 0), (0, 1), (0, 3), (2, 0)];
+        for (cell, expect) in cells.into_iter().zip(expect) {
+            let position = view.position_at(&file_buffer, &options, cell.0, cell.1);
+            assert_eq!(position, expect, "nowrap {:?}", cell);
+        }
+        view.scroll_x = 2;
+        assert_eq!(view.position_at(&file_buffer, &options, 3, 1), (1, 5));
+
+        // "hello world" wraps after "hello ", "world" following the "> " marker
+        view.scroll_x = 0;
+        view.scroll_y = 1;
+        options.wrap = true;
+        options.showbreak = "> ".to_string();
+        let cells = [(2, 0), (7, 0), (0, 1), (2, 1), (4, 1), (5, 2), (0, 5)];
+        let expect = [(1, 2), (1, 5), (1, 6), (1, 6), (1, 8), (2, 1), (2, 1)];
+        for (cell, expect) in cells.into_iter().zip(expect) {
+            let position = view.position_at(&file_buffer, &options, cell.0, cell.1);
+            assert_eq!(position, expect, "wrap {:?}", cell);
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use std::ops::Range;
+
+use crate::text::{display_column, grapheme_width, graphemes};
+
+/// Splits a line into the ranges of characters of its display lines, `width` cells wide.
+///
+/// Lines break after the last blank that fits, or between any graphemes of a word longer than
+/// the display line. The display lines continuing the line are `showbreak_width` cells
+/// narrower, leaving room for the `showbreak` marker. Tabs are expanded from the start of each
+/// display line.
+pub fn display_lines(
+    line: &[char],
+    width: u16,
+    showbreak_width: u16,
+    tabstop: u16,
+) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    let mut used = 0;
+    let mut last_blank = None;
+    for grapheme in graphemes(line) {
+        let available = if ranges.is_empty() {
+            width
+        } else {
+            width.saturating_sub(showbreak_width)
+        };
+        let mut width = grapheme_width(&line[grapheme.clone()], used, tabstop);
+        if used + width > usize::max(available as usize, 1) && grapheme.start > start {
+            let end = last_blank.take().unwrap_or(grapheme.start);
+            ranges.push(start..end);
+            start = end;
+            // Only the graphemes moved to the new display line are measured again
+            let moved = &line[start..grapheme.start];
+            used = display_column(moved, moved.len() as u16, tabstop) as usize;
+            width = grapheme_width(&line[grapheme.clone()], used, tabstop);
+        }
+        used += width;
+        if line[grapheme.clone()] == [' '] {
+            last_blank = Some(grapheme.end);
+        }
+    }
+    ranges.push(start..line.len());
+    ranges
+}
+
+/// Index of the display line holding the given column.
+pub fn display_line_index(ranges: &[Range<usize>], column: u16) -> usize {
+    ranges
+        .iter()
+        .rposition(|range| range.start <= column as usize)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_lines_should_break_at_word_boundaries() {
+        let cases = [
+            ("", 10, 0, vec![(0, 0)]),
+            ("short line", 10, 0, vec![(0, 10)]),
+            ("hello world again", 10, 0, vec![(0, 6), (6, 12), (12, 17)]),
+            ("hello world again", 10, 2, vec![(0, 6), (6, 12), (12, 17)]),
+            ("abcdefghijkl", 5, 0, vec![(0, 5), (5, 10), (10, 12)]),
+            (
+                "abcdefghijkl",
+                5,
+                2,
+                vec![(0, 5), (5, 8), (8, 11), (11, 12)],
+            ),
+            ("日本語のテキスト", 6, 0, vec![(0, 3), (3, 6), (6, 8)]),
+        ];
+        for (line, width, showbreak_width, expected) in cases {
+            let line: Vec<char> = line.chars().collect();
+            let ranges: Vec<(usize, usize)> = display_lines(&line, width, showbreak_width, 8)
+                .into_iter()
+                .map(|range| (range.start, range.end))
+                .collect();
+            assert_eq!(ranges, expected, "{:?}", line);
+        }
+    }
+}